@@ -1,6 +1,7 @@
 use std::env::set_current_dir;
 use std::fs;
-use stalfos_vm::assembler::assembler::parse_binary;
+use stalfos_vm::assembler::assembler::try_parse_binary;
+use stalfos_vm::debugger::debugger::Debugger;
 use stalfos_vm::stalfos;
 use std::fs::File;
 use std::io::Read;
@@ -9,19 +10,27 @@ use std::io::Read;
 * Stalfos : Stalfos Virtual Machine
 * Copyright (C) 2022 Alexander Walker
 
-* Usage: stalfos <inputfile.sta>
+* Usage: stalfos <inputfile.stf> [-d, --debug]
 */
 fn main() {
     //this is a test to see if i can connect to github
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let debug = args.iter().any(|arg| arg == "-d" || arg == "--debug");
+    args.retain(|arg| arg != "-d" && arg != "--debug");
     if args.len() < 2 {
-        println!("Usage: stalfos-vm <program>");
+        println!("Usage: stalfos-vm <program> [-d, --debug]");
         return;
     }
 
     let path = args[1].clone();
 
-    let executing_file =  fs::canonicalize(path.clone()).unwrap();
+    let executing_file = match fs::canonicalize(path.clone()) {
+        Ok(file) => file,
+        Err(error) => {
+            eprintln!("could not read {}: {}", path, error);
+            std::process::exit(1);
+        }
+    };
     let dir =executing_file.parent();
 
     let dirset=set_current_dir(dir.unwrap());
@@ -29,12 +38,37 @@ fn main() {
 
 
 
-    let mut file = File::open(path).unwrap();
+    // libraries are loaded relative to the program, so open it by its absolute path after moving there
     let mut buffer = Vec::new();
+    let read = File::open(&executing_file).and_then(|mut file| file.read_to_end(&mut buffer));
+    if let Err(error) = read {
+        eprintln!("could not read {}: {}", path, error);
+        std::process::exit(1);
+    }
 
+    let program = match try_parse_binary(buffer) {
+        Ok((program, _)) => program,
+        Err(error) => {
+            eprintln!("{} is corrupt: {}", path, error);
+            std::process::exit(1);
+        }
+    };
 
-    file.read_to_end(&mut buffer).unwrap();
-    let (program, _) = parse_binary(buffer);
+    if debug {
+        let mut debugger = match Debugger::new(program) {
+            Ok(debugger) => debugger,
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        };
+        let stdin = std::io::stdin();
+        debugger.repl(&mut stdin.lock(), &mut std::io::stdout()).unwrap();
+        return;
+    }
 
-    stalfos::VM::run_new(program);
+    if let Err(error) = stalfos::VM::run_new(program) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
 }
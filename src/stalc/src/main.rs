@@ -1,14 +1,16 @@
 use std::borrow::Borrow;
 use std::fs::File;
 use std::io::Read;
-use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::asm_parser::asm_parser::parse_source;
 use stalfos_vm::assembler::assembler;
+use stalfos_vm::disassembler::disassembler::disassemble;
 
 /*
 * STALC : Stalfos ASM (sta) Compiler
 * Copyright (C) 2022 Alexander Walker
 
 * Usage: stacl <inputfile.sta> <outputfile.stf> [-r,--run] [--check] [-d, --debug]
+*        stacl <inputfile.stf> <outputfile.sta> --disassemble [--check]
 */
 fn main() {
 
@@ -24,6 +26,7 @@ fn main() {
     let mut run = false;
     let mut check = false;
     let mut debug = false;
+    let mut disassemble_mode = false;
     for i in 3..args.len() {
         if args[i] == "-r" || args[i] == "--run" {
             run = true;
@@ -33,23 +36,44 @@ fn main() {
         else if args[i] == "-d" || args[i] == "--debug" {
             debug = true;
         }
+        else if args[i] == "--disassemble" {
+            disassemble_mode = true;
+        }
 
     }
 
     let infile = &args[1];
     let outfile = &args[2];
 
+    if disassemble_mode {
+        disassemble_file(infile, outfile, check);
+        return;
+    }
+
     //read input file
     let mut input = String::new();
     let mut file = File::open(infile).expect("file not found");
     file.read_to_string(&mut input).expect("something went wrong reading the file");
 
-    let (ns,ops) = parse_string(input);
+    let (ns,ops) = match parse_source(infile, &input) {
+        Ok(result) => result,
+        Err(diagnostics) => {
+            for diagnostic in &diagnostics {
+                eprintln!("{}\n", diagnostic);
+            }
+            let plural = if diagnostics.len() == 1 { "" } else { "s" };
+            eprintln!("error: could not assemble {} due to {} previous error{}", infile, diagnostics.len(), plural);
+            std::process::exit(1);
+        }
+    };
 
     let binary = assembler::assemble(ops.borrow(),ns.clone());
 
     if check {
-        let (new_ops,_) =assembler::parse_binary(binary.clone());
+        let (new_ops, _) = match assembler::try_parse_binary(binary.clone()) {
+            Ok(result) => result,
+            Err(error) => panic!("Binary is invalid: {}", error),
+        };
         let new_binary = assembler::assemble(new_ops.borrow(),ns.clone());
         if binary != new_binary {
             println!("{:?}", binary);
@@ -62,10 +86,54 @@ fn main() {
     assembler::write_to_file(binary.borrow(), outfile);
 
     if run {
-        if debug {
-            stalfos_vm::stalfos::VM::run_new_debug(ops);
+        let result = if debug {
+            stalfos_vm::stalfos::VM::run_new_debug(ops)
         } else {
-            stalfos_vm::stalfos::VM::run_new(ops);
+            stalfos_vm::stalfos::VM::run_new(ops)
+        };
+
+        if let Err(error) = result {
+            eprintln!("{}", error);
+            std::process::exit(1);
         }
     }
 }
+
+// reads a .stf/.stalib binary and writes it back out as .sta source
+fn disassemble_file(infile: &String, outfile: &String, check: bool) {
+    let mut binary = Vec::new();
+    let mut file = File::open(infile).expect("file not found");
+    file.read_to_end(&mut binary).expect("something went wrong reading the file");
+
+    let (ops, ns) = match assembler::try_parse_binary(binary) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("{} is corrupt: {}", infile, error);
+            std::process::exit(1);
+        }
+    };
+
+    let source = disassemble(&ops, &ns);
+
+    if check {
+        //the disassembled source must assemble to exactly what a fresh assemble of the decoded program gives
+        let (new_ns, new_ops) = match parse_source(outfile, &source) {
+            Ok(result) => result,
+            Err(diagnostics) => {
+                for diagnostic in &diagnostics {
+                    eprintln!("{}\n", diagnostic);
+                }
+                panic!("Disassembly is invalid");
+            }
+        };
+        let binary = assembler::assemble(ops.borrow(), ns);
+        let new_binary = assembler::assemble(new_ops.borrow(), new_ns);
+        if binary != new_binary {
+            println!("{:?}", binary);
+            println!("{:?}", new_binary);
+            panic!("Disassembly is invalid");
+        }
+    }
+
+    std::fs::write(outfile, source).expect("something went wrong writing the file");
+}
@@ -0,0 +1,26 @@
+use std::process::Command;
+
+#[test]
+fn run_prints_guest_output() {
+    let dir = std::env::temp_dir().join(format!("stalc_run_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("print.sta");
+    std::fs::write(
+        &source,
+        "JMP_SCAN\n.main\nCONST_S 1 \"hello from the guest\"\nLOADD 1\nSYSCALLD 3\nSYSCALL 2 0\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_stalc"))
+        .arg(&source)
+        .arg(dir.join("print.stf"))
+        .arg("--run")
+        .output()
+        .unwrap();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.ends_with("hello from the guest\nVM ended with exit code 1\n"), "{}", stdout);
+}
@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use stalfos_benches::guest_programs;
+use stalfos_vm::stalfos::ops::Operator;
+use stalfos_vm::stalfos::VM;
+
+const ITERATIONS: u32 = 10_000;
+
+fn bench(c: &mut Criterion, name: &str, program: Vec<Operator>) {
+    c.bench_function(name, |b| b.iter(|| VM::run_new(program.clone()).unwrap()));
+}
+
+fn bench_guest_programs(c: &mut Criterion) {
+    bench(
+        c,
+        "arithmetic_loop",
+        guest_programs::arithmetic_loop(ITERATIONS),
+    );
+    bench(
+        c,
+        "byte_manipulation",
+        guest_programs::byte_manipulation(ITERATIONS),
+    );
+    bench(
+        c,
+        "allocation_churn",
+        guest_programs::allocation_churn(ITERATIONS),
+    );
+    bench(
+        c,
+        "exception_unwinding",
+        guest_programs::exception_unwinding(ITERATIONS),
+    );
+
+    // LIBLOAD reads the library from disk on every run, the calls dominate at this many iterations
+    let dir = std::env::temp_dir().join(format!("stalfos_benches_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let library = guest_programs::install_library(&dir);
+    bench(
+        c,
+        "library_calls",
+        guest_programs::library_calls(&library, ITERATIONS),
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+criterion_group!(benches, bench_guest_programs);
+criterion_main!(benches);
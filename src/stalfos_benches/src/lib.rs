@@ -0,0 +1,169 @@
+pub mod guest_programs {
+    use stalfos_vm::assembler::assembler;
+    use stalfos_vm::stalfos::ops::Operator;
+    use std::path::Path;
+
+    // name of the library built by install_library, and of its .stalib file
+    pub const LIBRARY_NAMESPACE: &str = "stalfos_bench";
+
+    ///
+    /// Wraps body in a main that runs it iterations times. The loop counter is on top of the stack
+    /// whenever body starts, and body must leave it there. functions are placed after main.
+    ///
+    fn counted_loop(
+        setup: Vec<Operator>,
+        body: Vec<Operator>,
+        functions: Vec<Operator>,
+    ) -> Vec<Operator> {
+        let mut program = vec![Operator::JMP_SCAN, Operator::LABEL("main".to_string())];
+        program.extend(setup);
+        program.push(Operator::LABEL("loop".to_string()));
+        program.extend(body);
+        program.extend(vec![
+            Operator::PUSH(1),
+            Operator::SWAP,
+            Operator::SUBu,
+            Operator::DUP,
+            Operator::BRne("loop".to_string()),
+            Operator::RET,
+        ]);
+        program.extend(functions);
+        program
+    }
+
+    // a linear congruential generator over an accumulator kept under the counter
+    pub fn arithmetic_loop(iterations: u32) -> Vec<Operator> {
+        counted_loop(
+            vec![Operator::PUSH(1), Operator::PUSH(iterations)],
+            vec![
+                Operator::SWAP,
+                Operator::PUSH(31),
+                Operator::MULu,
+                Operator::PUSH(7),
+                Operator::ADDu,
+                Operator::PUSH(1_000_003),
+                Operator::SWAP,
+                Operator::MODu,
+                Operator::PUSH(0x5A5A),
+                Operator::XOR,
+                Operator::SWAP,
+            ],
+            vec![],
+        )
+    }
+
+    // writes four bytes spread over a 4 word allocation and xors them back together
+    pub fn byte_manipulation(iterations: u32) -> Vec<Operator> {
+        counted_loop(
+            vec![Operator::ALLOC(1, 4), Operator::PUSH(iterations)],
+            vec![
+                Operator::SETBYTE(1, 0, 0x12),
+                Operator::SETBYTE(1, 5, 0x34),
+                Operator::SETBYTE(1, 10, 0x56),
+                Operator::SETBYTE(1, 15, 0x78),
+                Operator::GETBYTE(1, 0),
+                Operator::GETBYTE(1, 5),
+                Operator::XOR,
+                Operator::GETBYTE(1, 10),
+                Operator::XOR,
+                Operator::GETBYTE(1, 15),
+                Operator::XOR,
+                Operator::POP,
+            ],
+            vec![],
+        )
+    }
+
+    // calls a function that makes and frees allocations of different sizes, RET frees the rest
+    pub fn allocation_churn(iterations: u32) -> Vec<Operator> {
+        counted_loop(
+            vec![Operator::PUSH(iterations)],
+            vec![Operator::CALL("churn".to_string())],
+            vec![
+                Operator::LABEL("churn".to_string()),
+                Operator::ALLOC(1, 8),
+                Operator::ALLOC(2, 3),
+                Operator::CONST_S(3, "a string constant for the allocator".to_string()),
+                Operator::DEALLOC(1),
+                Operator::PUSH(5),
+                Operator::DNEW,
+                Operator::DDEALLOC,
+                Operator::ALLOC(4, 16),
+                Operator::DEALLOC(2),
+                Operator::RET,
+            ],
+        )
+    }
+
+    ///
+    /// The library called by library_calls. mix hashes the value on top of the stack and returns
+    /// it as a 1 word result.
+    ///
+    pub fn library() -> Vec<Operator> {
+        vec![
+            Operator::JMP_DEF("mix".to_string(), 2),
+            Operator::LABEL("JT_END".to_string()),
+            Operator::LABEL("mix".to_string()),
+            Operator::PUSH(31),
+            Operator::MULu,
+            Operator::PUSH(7),
+            Operator::ADDu,
+            Operator::PUSH(1),
+            Operator::RET,
+        ]
+    }
+
+    ///
+    /// Assembles library() into dir and returns the name to load it by, which is its path without
+    /// the .stalib extension.
+    ///
+    pub fn install_library(dir: &Path) -> String {
+        let binary = assembler::assemble(&library(), LIBRARY_NAMESPACE.to_string());
+        let path = dir.join(format!("{}.stalib", LIBRARY_NAMESPACE));
+        assembler::write_to_file(&binary, path.to_str().unwrap());
+        dir.join(LIBRARY_NAMESPACE).to_str().unwrap().to_string()
+    }
+
+    // loads the library at library (see install_library) and calls mix with the counter each iteration
+    pub fn library_calls(library: &str, iterations: u32) -> Vec<Operator> {
+        counted_loop(
+            vec![
+                Operator::LIBLOAD(library.to_string()),
+                Operator::PUSH(iterations),
+            ],
+            vec![
+                Operator::LIBCALL(library.to_string(), "mix".to_string()),
+                // the result size, then the result
+                Operator::POP,
+                Operator::POP,
+            ],
+            vec![],
+        )
+    }
+
+    // throws from 3 calls deep, unwinding the frames (and their allocations) back to a catch in main
+    pub fn exception_unwinding(iterations: u32) -> Vec<Operator> {
+        counted_loop(
+            vec![Operator::PUSH(iterations)],
+            vec![
+                Operator::EXCEPT_CATCH("caught".to_string()),
+                Operator::CALL("outer".to_string()),
+                Operator::LABEL("caught".to_string()),
+                Operator::EXCEPT_END,
+            ],
+            vec![
+                Operator::LABEL("outer".to_string()),
+                Operator::ALLOC(1, 4),
+                Operator::CALL("middle".to_string()),
+                Operator::RET,
+                Operator::LABEL("middle".to_string()),
+                Operator::ALLOC(2, 4),
+                Operator::CALL("inner".to_string()),
+                Operator::RET,
+                Operator::LABEL("inner".to_string()),
+                Operator::EXCEPT_THROWC(7),
+                Operator::RET,
+            ],
+        )
+    }
+}
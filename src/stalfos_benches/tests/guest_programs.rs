@@ -0,0 +1,44 @@
+use stalfos_benches::guest_programs;
+use stalfos_vm::stalfos::VM;
+
+#[test]
+fn arithmetic_loop_matches_the_host() {
+    let mut expected: u32 = 1;
+    for _ in 0..100 {
+        expected = ((expected * 31 + 7) % 1_000_003) ^ 0x5A5A;
+    }
+
+    let vm = VM::run_new(guest_programs::arithmetic_loop(100)).unwrap();
+    assert_eq!(vm.stack, vec![expected, 0]);
+}
+
+#[test]
+fn programs_leave_only_the_counter() {
+    for program in [
+        guest_programs::byte_manipulation(100),
+        guest_programs::allocation_churn(100),
+        guest_programs::exception_unwinding(100),
+    ] {
+        let vm = VM::run_new(program).unwrap();
+        assert_eq!(vm.stack, vec![0]);
+        assert!(vm.exception.is_none());
+    }
+}
+
+#[test]
+fn allocation_churn_frees_every_allocation() {
+    let vm = VM::run_new(guest_programs::allocation_churn(100)).unwrap();
+    assert!(vm.static_alloc_table.is_empty());
+    assert!(vm.allocator.allocations().is_empty());
+}
+
+#[test]
+fn library_calls_round_trip() {
+    let dir = std::env::temp_dir().join(format!("stalfos_benches_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let library = guest_programs::install_library(&dir);
+
+    let result = VM::run_new(guest_programs::library_calls(&library, 100));
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(result.unwrap().stack, vec![0]);
+}
@@ -0,0 +1,54 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::stalfos::VM;
+
+// adds 3 to an accumulator 100000 times, branching back to a label each iteration
+const COUNTDOWN: &str = "
+JMP_SCAN
+.main
+PUSH 0
+PUSH 100000
+.loop
+SWAP
+PUSH 3
+ADDu
+SWAP
+PUSH 1
+SWAP
+SUBu
+DUP
+BRne loop
+RET
+";
+
+// calls a function that stores a string constant 20000 times
+const CALLS: &str = "
+JMP_SCAN
+.main
+PUSH 20000
+.loop
+CALL store
+PUSH 1
+SWAP
+SUBu
+DUP
+BRne loop
+RET
+.store
+CONST_S 1 \"a string that is long enough to need an allocation to clone\"
+GETLEN 1
+POP
+RET
+";
+
+fn bench_loops(c: &mut Criterion) {
+    for (name, source) in [("countdown", COUNTDOWN), ("calls", CALLS)] {
+        let program = parse_string(source.to_string()).unwrap().1;
+        c.bench_function(name, |b| {
+            b.iter(|| VM::run_new(program.clone()).unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, bench_loops);
+criterion_main!(benches);
@@ -0,0 +1,119 @@
+pub mod allocator {
+    use std::collections::BTreeMap;
+
+    ///
+    /// First fit allocator over vm.memory. Free blocks are kept in address order and merged with
+    /// their neighbours when an allocation is freed, so memory only grows when no free block is
+    /// big enough. Blocks are (start, size) in words.
+    ///
+    #[derive(Debug, Clone, Default)]
+    pub struct Allocator {
+        // start -> size of each free block. adjacent free blocks are always merged
+        free: BTreeMap<usize, usize>,
+        // start -> size of each live allocation. zero sized allocations are not tracked
+        used: BTreeMap<usize, usize>,
+    }
+
+    impl Allocator {
+        pub fn new() -> Allocator {
+            Allocator {
+                free: BTreeMap::new(),
+                used: BTreeMap::new(),
+            }
+        }
+
+        ///
+        /// Finds size zeroed words, growing memory if no free block fits. Returns None if memory
+        /// would have to grow past max_words, in which case nothing changes.
+        ///
+        pub fn allocate(
+            &mut self,
+            size: usize,
+            memory: &mut Vec<u32>,
+            max_words: usize,
+        ) -> Option<usize> {
+            if size == 0 {
+                return Some(memory.len());
+            }
+
+            let fit = self
+                .free
+                .iter()
+                .find(|(_, free_size)| **free_size >= size)
+                .map(|(start, free_size)| (*start, *free_size));
+
+            let start = match fit {
+                Some((start, free_size)) => {
+                    self.free.remove(&start);
+                    if free_size > size {
+                        self.free.insert(start + size, free_size - size);
+                    }
+                    start
+                }
+                None => {
+                    // nothing fits, grow memory. a free block at the very end is used as the start of the new block
+                    let start = match self.free.iter().next_back() {
+                        Some((start, free_size)) if start + free_size == memory.len() => *start,
+                        _ => memory.len(),
+                    };
+                    let end = start.checked_add(size)?;
+                    if end > max_words {
+                        return None;
+                    }
+                    self.free.remove(&start);
+                    memory.resize(end, 0);
+                    start
+                }
+            };
+
+            for word in memory[start..start + size].iter_mut() {
+                *word = 0;
+            }
+            self.used.insert(start, size);
+            Some(start)
+        }
+
+        ///
+        /// Returns an allocation to the free list, merging it with the free blocks either side.
+        /// Returns false (and does nothing) if there is no live allocation of that size at start.
+        ///
+        pub fn free(&mut self, start: usize, size: usize) -> bool {
+            if size == 0 {
+                return true;
+            }
+            if self.used.get(&start) != Some(&size) {
+                return false;
+            }
+            self.used.remove(&start);
+
+            let mut start = start;
+            let mut size = size;
+
+            let previous = self.free.range(..start).next_back().map(|(s, l)| (*s, *l));
+            if let Some((previous_start, previous_size)) = previous {
+                if previous_start + previous_size == start {
+                    self.free.remove(&previous_start);
+                    start = previous_start;
+                    size += previous_size;
+                }
+            }
+
+            if let Some(next_size) = self.free.remove(&(start + size)) {
+                size += next_size;
+            }
+
+            self.free.insert(start, size);
+            true
+        }
+
+        // (start, size) of each free block, in address order
+        pub fn free_blocks(&self) -> Vec<(usize, usize)> {
+            self.free.iter().map(|(start, size)| (*start, *size)).collect()
+        }
+
+        // (start, size) of each live allocation, in address order
+        pub fn allocations(&self) -> Vec<(usize, usize)> {
+            self.used.iter().map(|(start, size)| (*start, *size)).collect()
+        }
+    }
+}
@@ -1,432 +1,895 @@
 pub mod asm_parser {
     use crate::stalfos::ops::Operator;
+    use std::cell::Cell;
+    use std::fmt;
     use std::str::FromStr;
 
-    pub fn parse_string(string: String) -> (String, Vec<Operator>) {
-        //read character at a time, creating a new string each time to parse until eol or comment
-        let mut current_line = String::new();
-        // let mut current_op = Operator::new/();
+    ///
+    /// A problem found while parsing a .sta file. line and column are 1-based and point at the
+    /// offending token. Display renders it rustc-style, with a caret under the token.
+    ///
+    #[derive(Debug, Clone)]
+    pub struct Diagnostic {
+        pub file_name: String,
+        pub line: usize,
+        pub column: usize,
+        pub token: String,
+        pub message: String,
+        // shown next to the caret, eg "expected 3 operands for SETBYTE"
+        pub hint: String,
+        pub source_line: String,
+    }
+
+    impl fmt::Display for Diagnostic {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let gutter = " ".repeat(self.line.to_string().len());
+
+            // keep tabs so the caret lines up with the source as the terminal renders it
+            let mut caret_offset = String::new();
+            for c in self.source_line.chars().take(self.column - 1) {
+                caret_offset.push(if c == '\t' { '\t' } else { ' ' });
+            }
+            let remaining = self.source_line.chars().count().saturating_sub(self.column - 1);
+            let caret_length = self.token.chars().count().min(remaining).max(1);
+
+            writeln!(f, "error: {}", self.message)?;
+            writeln!(f, "{}--> {}:{}:{}", gutter, self.file_name, self.line, self.column)?;
+            writeln!(f, "{} |", gutter)?;
+            writeln!(f, "{} | {}", self.line, self.source_line)?;
+            write!(f, "{} | {}{} {}", gutter, caret_offset, "^".repeat(caret_length), self.hint)
+        }
+    }
+
+    impl std::error::Error for Diagnostic {}
+
+    // a whitespace separated segment of a line, quoted strings are a single token
+    #[derive(Debug, Clone)]
+    struct Token {
+        text: String,
+        line: usize,
+        column: usize,
+    }
+
+    struct Line<'a> {
+        file_name: &'a str,
+        source_lines: &'a [String],
+        tokens: Vec<Token>,
+        // set by expect_operands, anything after this many operands is an error
+        expected_operands: Cell<usize>,
+    }
+
+    pub fn parse_string(string: String) -> Result<(String, Vec<Operator>), Vec<Diagnostic>> {
+        parse_source("<string>", &string)
+    }
+
+    ///
+    /// Parses .sta source into its namespace (empty if there is no #<ns> line) and operations.
+    /// file_name is only used to label diagnostics. Every line is parsed, so all of the problems
+    /// in the file are returned at once.
+    ///
+    pub fn parse_source(
+        file_name: &str,
+        source: &str,
+    ) -> Result<(String, Vec<Operator>), Vec<Diagnostic>> {
+        let chars: Vec<char> = source.chars().collect();
+        let source_lines = split_lines(&chars);
+        let (statements, mut diagnostics) = tokenize(file_name, &chars, &source_lines);
+
         let mut ops = vec![];
-        let file_length = string.len();
-        let mut lines: Vec<String> = vec![];
+        let mut ns_name = String::new();
+        for (index, tokens) in statements.into_iter().enumerate() {
+            //check first line for NS declaration
+            if index == 0 {
+                let first_line = tokens
+                    .iter()
+                    .map(|t| t.text.clone())
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                if first_line.starts_with("#<") && first_line.ends_with(">") {
+                    ns_name = first_line.split("#<").collect::<Vec<&str>>()[1]
+                        .split(">")
+                        .collect::<Vec<&str>>()[0]
+                        .to_string();
+                    continue;
+                }
+            }
+
+            let line = Line {
+                file_name,
+                source_lines: &source_lines,
+                tokens,
+                expected_operands: Cell::new(0),
+            };
+            match parse_line(&line) {
+                Ok(op) => ops.push(op),
+                Err(diagnostic) => diagnostics.push(*diagnostic),
+            }
+        }
+
+        if !diagnostics.is_empty() {
+            // tokenize reports unterminated strings before any line is parsed
+            diagnostics.sort_by_key(|d| (d.line, d.column));
+            return Err(diagnostics);
+        }
+
+        return Ok((ns_name, ops));
+    }
+
+    // \r, \n, \r\n and \n\r all end a line. returns how many chars the line ending at i uses
+    fn line_ending_length(chars: &[char], i: usize) -> usize {
+        let next = chars[i];
+        if i + 1 < chars.len() && (chars[i + 1] == '\r' || chars[i + 1] == '\n') && chars[i + 1] != next {
+            return 2;
+        }
+        1
+    }
+
+    fn split_lines(chars: &[char]) -> Vec<String> {
+        let mut lines = vec![];
+        let mut current_line = String::new();
         let mut i = 0;
-        for _ in 0..file_length {
-            let _next = string.chars().nth(i);
-            if _next.is_none() {
-                break;
+        while i < chars.len() {
+            if chars[i] == '\r' || chars[i] == '\n' {
+                i += line_ending_length(chars, i);
+                lines.push(current_line);
+                current_line = String::new();
+            } else {
+                current_line.push(chars[i]);
+                i += 1;
             }
-            let mut next = _next.unwrap();
+        }
+        lines.push(current_line);
+        lines
+    }
+
+    // splits the source into statements (one per non-empty line) of tokens, dropping comments
+    fn tokenize(
+        file_name: &str,
+        chars: &[char],
+        source_lines: &[String],
+    ) -> (Vec<Vec<Token>>, Vec<Diagnostic>) {
+        let mut statements: Vec<Vec<Token>> = vec![];
+        let mut diagnostics = vec![];
+        let mut current_statement: Vec<Token> = vec![];
+        let mut current_token: Option<Token> = None;
+        let mut line = 1;
+        let mut column = 1;
+        let mut i = 0;
+        while i < chars.len() {
+            let next = chars[i];
 
             //this wont interfere with string parsing because it will read until matching quote without hitting this case
             if next == ';' {
-                //comment, skip to end of line ending with \r \n or \r\n
-                while next != '\r' && next != '\n' {
+                //comment, skip to end of line. the line ending itself is handled by the next iteration
+                while i < chars.len() && chars[i] != '\r' && chars[i] != '\n' {
                     i += 1;
-                    next = string.chars().nth(i).unwrap();
-                    if next == '\r'
-                        && (string.chars().nth(i + 1).is_some()
-                            && string.chars().nth(i + 1).unwrap() == '\n')
-                    {
-                        i += 1;
-                    } else if next == '\n'
-                        && (string.chars().nth(i + 1).is_some()
-                            && string.chars().nth(i + 1).unwrap() == '\r')
-                    {
-                        i += 1;
-                    }
-                }
-
-                if current_line.len() > 0 {
-                    lines.push(current_line);
-                    current_line = String::new();
                 }
             } else if next == '\r' || next == '\n' {
-                //end of line, parse current line
-                i += 1;
-                let _next = string.chars().nth(i);
-                let valid = _next.is_some();
-                if valid
-                    && _next.unwrap() == '\r'
-                    && (string.chars().nth(i + 1).is_some()
-                        && string.chars().nth(i + 1).unwrap() == '\n')
-                {
-                    i += 1;
-                } else if next == '\n'
-                    && (string.chars().nth(i + 1).is_some()
-                        && string.chars().nth(i + 1).unwrap() == '\r')
-                {
-                    i += 1;
+                //end of line, finish the current statement
+                if let Some(token) = current_token.take() {
+                    current_statement.push(token);
                 }
-
-                if current_line.len() > 0 {
-                    lines.push(current_line);
-                    current_line = String::new();
+                if !current_statement.is_empty() {
+                    statements.push(current_statement);
+                    current_statement = vec![];
                 }
+                i += line_ending_length(chars, i);
+                line += 1;
+                column = 1;
+            } else if next == ' ' || next == '\t' {
+                if let Some(token) = current_token.take() {
+                    current_statement.push(token);
+                }
+                i += 1;
+                column += 1;
             } else if next == '"' {
-                //consume string and add it to line
-                let mut string_builder = String::new();
+                //consume string and add it to the token. strings may span lines
+                let (start_line, start_column) = (line, column);
+                let mut token = current_token.take().unwrap_or(Token {
+                    text: String::new(),
+                    line,
+                    column,
+                });
+                token.text.push(next);
                 i += 1;
-                string_builder.push(next);
-                let mut next = string.chars().nth(i).unwrap();
-                while next != '"' || (next == '"' && string.chars().nth(i - 1).unwrap() == '\\') {
-                    string_builder.push(next);
+                column += 1;
+
+                let mut closed = false;
+                while i < chars.len() {
+                    let c = chars[i];
                     i += 1;
-                    next = string.chars().nth(i).unwrap();
-                }
 
-                string_builder.push(next);
-                string_builder = string_builder.replace("\\n", "\n");
-                string_builder = string_builder.replace("\\r", "\r");
+                    // \n \r \t \0 \" and \\ are escapes, any other backslash is kept as is
+                    let escaped = match chars.get(i).filter(|_| c == '\\') {
+                        Some('n') => Some('\n'),
+                        Some('r') => Some('\r'),
+                        Some('t') => Some('\t'),
+                        Some('0') => Some('\0'),
+                        Some('"') => Some('"'),
+                        Some('\\') => Some('\\'),
+                        _ => None,
+                    };
+                    if let Some(escaped) = escaped {
+                        token.text.push(escaped);
+                        i += 1;
+                        column += 2;
+                        continue;
+                    }
 
-                //remove starting and ending quotes from string if they exist
-                // if string_builder.starts_with("\"") {
-                //     string_builder.pop();
-                //     string_builder.remove(0);
-                // }
+                    token.text.push(c);
+                    if c == '"' {
+                        closed = true;
+                        column += 1;
+                        break;
+                    }
+                    if c == '\n' || (c == '\r' && (i >= chars.len() || chars[i] != '\n')) {
+                        line += 1;
+                        column = 1;
+                    } else {
+                        column += 1;
+                    }
+                }
 
-                current_line.push_str(&string_builder);
-                i += 1;
-                current_line.push_str(" ");
-                // i+=1;
+                if !closed {
+                    diagnostics.push(Diagnostic {
+                        file_name: file_name.to_string(),
+                        line: start_line,
+                        column: start_column,
+                        token: "\"".to_string(),
+                        message: "unterminated string".to_string(),
+                        hint: "string starts here".to_string(),
+                        source_line: source_lines[start_line - 1].clone(),
+                    });
+                }
+
+                //finished quoted string, finish token and start new
+                current_statement.push(token);
             } else {
-                //add to current line
-                current_line.push(next);
+                //add to current token
+                match current_token.as_mut() {
+                    Some(token) => token.text.push(next),
+                    None => {
+                        current_token = Some(Token {
+                            text: next.to_string(),
+                            line,
+                            column,
+                        })
+                    }
+                }
                 i += 1;
+                column += 1;
             }
         }
 
-        //check first line for NS declaration
-        let mut ns_declaration = false;
-        let first_line = lines.get(0).unwrap().clone();
-        let mut ns_name = "";
-        if first_line.starts_with("#<") && first_line.ends_with(">") {
-            ns_declaration = true;
-            ns_name = first_line.split("#<").collect::<Vec<&str>>()[1]
-                .split(">")
-                .collect::<Vec<&str>>()[0];
+        if let Some(token) = current_token.take() {
+            current_statement.push(token);
+        }
+        if !current_statement.is_empty() {
+            statements.push(current_statement);
         }
 
-        let mut is_first_line = true;
-        for line in lines {
-            if is_first_line {
-                is_first_line = false;
-                if ns_declaration {
-                    continue;
-                }
-            }
+        (statements, diagnostics)
+    }
 
-            ops.push(get_operation_from_line(line));
+    fn parse_line(line: &Line) -> Result<Operator, Box<Diagnostic>> {
+        let operation = get_operation_from_line(line)?;
+
+        let expected = line.expected_operands.get();
+        if line.tokens.len() - 1 > expected {
+            let extra = &line.tokens[expected + 1];
+            return Err(line.diagnostic(
+                extra,
+                format!("unexpected operand '{}'", extra.text),
+                line.operand_count_hint(expected),
+            ));
         }
 
-        return (ns_name.to_string(), ops);
+        Ok(operation)
     }
 
-    fn get_operation_from_line(line: String) -> Operator {
-        //remove leading whitespace from line;
-        let trimmed_line = line.trim();
+    impl<'a> Line<'a> {
+        // boxed so the Result of every operand parser stays small, parse_source unboxes it
+        fn diagnostic(&self, token: &Token, message: String, hint: String) -> Box<Diagnostic> {
+            Box::new(Diagnostic {
+                file_name: self.file_name.to_string(),
+                line: token.line,
+                column: token.column,
+                token: token.text.clone(),
+                message,
+                hint,
+                source_line: self.source_lines[token.line - 1].clone(),
+            })
+        }
 
-        let segments: Vec<String> = get_segments_from_line(trimmed_line.to_string());
+        fn operand_count_hint(&self, count: usize) -> String {
+            let plural = if count == 1 { "" } else { "s" };
+            format!("expected {} operand{} for {}", count, plural, self.tokens[0].text)
+        }
 
-        let first_segment = &*segments.get(0).unwrap().clone().to_owned();
+        fn expect_operands(&self, count: usize) -> Result<(), Box<Diagnostic>> {
+            self.expected_operands.set(count);
+            if self.tokens.len() - 1 < count {
+                let last = &self.tokens[self.tokens.len() - 1];
+                return Err(self.diagnostic(
+                    last,
+                    format!("missing operand for {}", self.tokens[0].text),
+                    self.operand_count_hint(count),
+                ));
+            }
+            Ok(())
+        }
+
+        fn invalid_operand(&self, index: usize, expected: &str) -> Box<Diagnostic> {
+            let token = &self.tokens[index];
+            self.diagnostic(
+                token,
+                format!("invalid operand '{}'", token.text),
+                format!("expected {} for {}", expected, self.tokens[0].text),
+            )
+        }
+
+        fn usize_operand(&self, index: usize) -> Result<usize, Box<Diagnostic>> {
+            match str_to_usize(&self.tokens[index].text) {
+                Some(v) => Ok(v),
+                None => Err(self.invalid_operand(index, "an unsigned integer")),
+            }
+        }
+
+        fn u32_operand(&self, index: usize) -> Result<u32, Box<Diagnostic>> {
+            match str_to_u32(&self.tokens[index].text) {
+                Some(v) => Ok(v),
+                None => Err(self.invalid_operand(index, "a 32 bit unsigned integer")),
+            }
+        }
+
+        fn u8_operand(&self, index: usize) -> Result<u8, Box<Diagnostic>> {
+            match str_to_u8(&self.tokens[index].text) {
+                Some(v) => Ok(v),
+                None => Err(self.invalid_operand(index, "a byte (0-255)")),
+            }
+        }
+
+        fn f32_operand(&self, index: usize) -> Result<f32, Box<Diagnostic>> {
+            match str_to_f32(&self.tokens[index].text) {
+                Some(v) => Ok(v),
+                None => Err(self.invalid_operand(index, "a float")),
+            }
+        }
+
+        fn i32_operand(&self, index: usize) -> Result<i32, Box<Diagnostic>> {
+            match str_to_i32(&self.tokens[index].text) {
+                Some(v) => Ok(v),
+                None => Err(self.invalid_operand(index, "a 32 bit signed integer")),
+            }
+        }
+
+        fn bool_operand(&self, index: usize) -> Result<bool, Box<Diagnostic>> {
+            match str_to_bool(&self.tokens[index].text) {
+                Some(v) => Ok(v),
+                None => Err(self.invalid_operand(index, "one of 0,1,t,f,true,false")),
+            }
+        }
+
+        fn string_operand(&self, index: usize) -> Result<String, Box<Diagnostic>> {
+            Ok(clean_string(self.tokens[index].text.clone()))
+        }
+    }
+
+    fn get_operation_from_line(line: &Line) -> Result<Operator, Box<Diagnostic>> {
+        let first_segment = &*line.tokens[0].text;
 
         match first_segment {
             "JMP_SCAN" => {
-                return Operator::JMP_SCAN;
+                return Ok(Operator::JMP_SCAN);
             }
             "PUSH" => {
-                return Operator::PUSH(str_to_u32(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::PUSH(line.u32_operand(1)?));
             }
             "LOAD" => {
-                return Operator::LOAD(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::LOAD(line.usize_operand(1)?));
             }
             "LOADD" => {
-                return Operator::LOADD(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::LOADD(line.usize_operand(1)?));
             }
             "CONST_U" => {
-                return Operator::CONST_U(
-                    str_to_usize(&*segments.get(1).unwrap()),
-                    str_to_u32(&*segments.get(2).unwrap()),
-                );
+                line.expect_operands(2)?;
+                return Ok(Operator::CONST_U(
+                    line.usize_operand(1)?,
+                    line.u32_operand(2)?,
+                ));
             }
             "CONST_F" => {
-                return Operator::CONST_F(
-                    str_to_usize(&*segments.get(1).unwrap()),
-                    str_to_f32(&*segments.get(2).unwrap()),
-                );
+                line.expect_operands(2)?;
+                return Ok(Operator::CONST_F(
+                    line.usize_operand(1)?,
+                    line.f32_operand(2)?,
+                ));
             }
             "CONST_I" => {
-                return Operator::CONST_I(
-                    str_to_usize(&*segments.get(1).unwrap()),
-                    str_to_i32(&*segments.get(2).unwrap()),
-                );
+                line.expect_operands(2)?;
+                return Ok(Operator::CONST_I(
+                    line.usize_operand(1)?,
+                    line.i32_operand(2)?,
+                ));
             }
             "CONST_B" => {
-                return Operator::CONST_B(
-                    str_to_usize(&*segments.get(1).unwrap()),
-                    str_to_bool(&*segments.get(2).unwrap()),
-                );
+                line.expect_operands(2)?;
+                return Ok(Operator::CONST_B(
+                    line.usize_operand(1)?,
+                    line.bool_operand(2)?,
+                ));
             }
             "CONST_S" => {
-                let us = str_to_usize(&*segments.get(1).unwrap());
-                let s = clean_string(segments.get(2).unwrap().clone());
+                line.expect_operands(2)?;
+                let us = line.usize_operand(1)?;
+                let s = line.string_operand(2)?;
 
-                return Operator::CONST_S(us, s);
+                return Ok(Operator::CONST_S(us, s));
             }
             "LOAD_CONST" => {
-                return Operator::LOAD_CONST(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::LOAD_CONST(line.usize_operand(1)?));
             }
             "POP" => {
-                return Operator::POP;
+                return Ok(Operator::POP);
             }
             "ALLOC" => {
-                return Operator::ALLOC(
-                    str_to_usize(&*segments.get(1).unwrap()),
-                    str_to_u32(&*segments.get(2).unwrap()),
-                );
+                line.expect_operands(2)?;
+                return Ok(Operator::ALLOC(
+                    line.usize_operand(1)?,
+                    line.u32_operand(2)?,
+                ));
+            }
+            "ALLOC_PERSIST" => {
+                line.expect_operands(2)?;
+                return Ok(Operator::ALLOC_PERSIST(
+                    line.usize_operand(1)?,
+                    line.u32_operand(2)?,
+                ));
             }
             "DEALLOC" => {
-                return Operator::DEALLOC(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::DEALLOC(line.usize_operand(1)?));
             }
             "POPS" => {
-                return Operator::POPS(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::POPS(line.usize_operand(1)?));
             }
             "GETLEN" => {
-                return Operator::GETLEN(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::GETLEN(line.usize_operand(1)?));
             }
             "GETBYTELEN" => {
-                return Operator::GETBYTELEN(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::GETBYTELEN(line.usize_operand(1)?));
             }
             "GETBYTE" => {
-                return Operator::GETBYTE(
-                    str_to_usize(&*segments.get(1).unwrap()),
-                    str_to_usize(&*segments.get(2).unwrap()),
-                );
+                line.expect_operands(2)?;
+                return Ok(Operator::GETBYTE(
+                    line.usize_operand(1)?,
+                    line.usize_operand(2)?,
+                ));
             }
             "GETWORD" => {
-                return Operator::GETWORD(
-                    str_to_usize(&*segments.get(1).unwrap()),
-                    str_to_usize(&*segments.get(2).unwrap()),
-                );
+                line.expect_operands(2)?;
+                return Ok(Operator::GETWORD(
+                    line.usize_operand(1)?,
+                    line.usize_operand(2)?,
+                ));
             }
             "SETBYTE" => {
-                return Operator::SETBYTE(
-                    str_to_usize(&*segments.get(1).unwrap()),
-                    str_to_usize(&*segments.get(2).unwrap()),
-                    str_to_u8(&*segments.get(3).unwrap()),
-                );
+                line.expect_operands(3)?;
+                return Ok(Operator::SETBYTE(
+                    line.usize_operand(1)?,
+                    line.usize_operand(2)?,
+                    line.u8_operand(3)?,
+                ));
             }
 
             "SETWORD" => {
-                return Operator::SETWORD(
-                    str_to_usize(&*segments.get(1).unwrap()),
-                    str_to_usize(&*segments.get(2).unwrap()),
-                    str_to_u32(&*segments.get(3).unwrap()),
-                );
+                line.expect_operands(3)?;
+                return Ok(Operator::SETWORD(
+                    line.usize_operand(1)?,
+                    line.usize_operand(2)?,
+                    line.u32_operand(3)?,
+                ));
             }
 
             "DUP" => {
-                return Operator::DUP;
+                return Ok(Operator::DUP);
             }
             "DUPO" => {
-                return Operator::DUPO(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::DUPO(line.usize_operand(1)?));
             }
 
             "SWAP" => {
-                return Operator::SWAP;
-            }
-            "ADDu" => return Operator::ADDu,
-            "ADDi" => return Operator::ADDi,
-            "ADDfi" => return Operator::ADDfi,
-            "ADDif" => return Operator::ADDif,
-            "ADDf" => return Operator::ADDf,
-            "SUBu" => return Operator::SUBu,
-            "SUBi" => return Operator::SUBi,
-            "SUBfi" => return Operator::SUBfi,
-            "SUBif" => return Operator::SUBif,
-            "SUBf" => return Operator::SUBf,
-            "MULu" => return Operator::MULu,
-            "MULi" => return Operator::MULi,
-            "MULfi" => return Operator::MULfi,
-            "MULif" => return Operator::MULif,
-            "MULf" => return Operator::MULf,
-            "DIVu" => return Operator::DIVu,
-            "DIVi" => return Operator::DIVi,
-            "DIVfi" => return Operator::DIVfi,
-            "DIVif" => return Operator::DIVif,
-            "DIVf" => return Operator::DIVf,
-            "MODu" => return Operator::MODu,
-            "MODi" => return Operator::MODi,
-            "MODfi" => return Operator::MODfi,
-            "MODif" => return Operator::MODif,
-            "MODf" => return Operator::MODf,
-            "ROR" => return Operator::ROR,
-            "ROL" => return Operator::ROL,
-            "LSR" => return Operator::LSR,
-            "ASR" => return Operator::ASR,
-            "LSL" => return Operator::LSL,
-            "ASL" => return Operator::ASL,
-            "NEG" => return Operator::NEG,
-            "AND" => return Operator::AND,
-            "OR" => return Operator::OR,
-            "NOR" => return Operator::NOR,
-            "XOR" => return Operator::XOR,
-            "NAND" => return Operator::NAND,
-            "CNT" => return Operator::CNT,
-            "CMP" => return Operator::CMP,
+                return Ok(Operator::SWAP);
+            }
+            "ADDu" => return Ok(Operator::ADDu),
+            "ADDi" => return Ok(Operator::ADDi),
+            "ADDfi" => return Ok(Operator::ADDfi),
+            "ADDif" => return Ok(Operator::ADDif),
+            "ADDf" => return Ok(Operator::ADDf),
+            "SUBu" => return Ok(Operator::SUBu),
+            "SUBi" => return Ok(Operator::SUBi),
+            "SUBfi" => return Ok(Operator::SUBfi),
+            "SUBif" => return Ok(Operator::SUBif),
+            "SUBf" => return Ok(Operator::SUBf),
+            "MULu" => return Ok(Operator::MULu),
+            "MULi" => return Ok(Operator::MULi),
+            "MULfi" => return Ok(Operator::MULfi),
+            "MULif" => return Ok(Operator::MULif),
+            "MULf" => return Ok(Operator::MULf),
+            "DIVu" => return Ok(Operator::DIVu),
+            "DIVi" => return Ok(Operator::DIVi),
+            "DIVfi" => return Ok(Operator::DIVfi),
+            "DIVif" => return Ok(Operator::DIVif),
+            "DIVf" => return Ok(Operator::DIVf),
+            "MODu" => return Ok(Operator::MODu),
+            "MODi" => return Ok(Operator::MODi),
+            "MODfi" => return Ok(Operator::MODfi),
+            "MODif" => return Ok(Operator::MODif),
+            "MODf" => return Ok(Operator::MODf),
+            "ROR" => return Ok(Operator::ROR),
+            "ROL" => return Ok(Operator::ROL),
+            "LSR" => return Ok(Operator::LSR),
+            "ASR" => return Ok(Operator::ASR),
+            "LSL" => return Ok(Operator::LSL),
+            "ASL" => return Ok(Operator::ASL),
+            "NEG" => return Ok(Operator::NEG),
+            "AND" => return Ok(Operator::AND),
+            "OR" => return Ok(Operator::OR),
+            "NOR" => return Ok(Operator::NOR),
+            "XOR" => return Ok(Operator::XOR),
+            "NAND" => return Ok(Operator::NAND),
+            "CNT" => return Ok(Operator::CNT),
+            "CMP" => return Ok(Operator::CMP),
             "JMP" => {
-                return Operator::JMP(clean_string(segments.get(1).unwrap().clone()));
+                line.expect_operands(1)?;
+                return Ok(Operator::JMP(line.string_operand(1)?));
             }
             "JMPo" => {
-                return Operator::JMPo(clean_string(segments.get(1).unwrap().clone()));
+                line.expect_operands(1)?;
+                return Ok(Operator::JMPo(line.string_operand(1)?));
             }
             "JMPe" => {
-                return Operator::JMPe(clean_string(segments.get(1).unwrap().clone()));
+                line.expect_operands(1)?;
+                return Ok(Operator::JMPe(line.string_operand(1)?));
             }
             "JMPne" => {
-                return Operator::JMPne(clean_string(segments.get(1).unwrap().clone()));
+                line.expect_operands(1)?;
+                return Ok(Operator::JMPne(line.string_operand(1)?));
             }
             "JMPs" => {
-                return Operator::JMPs(
-                    clean_string(segments.get(1).unwrap().clone()),
-                    clean_string(segments.get(2).unwrap().clone()),
-                );
+                line.expect_operands(2)?;
+                return Ok(Operator::JMPs(
+                    line.string_operand(1)?,
+                    line.string_operand(2)?,
+                ));
             }
             "JMP_DEF" => {
-                return Operator::JMP_DEF(
-                    clean_string(segments.get(1).unwrap().clone()),
-                    str_to_usize(&*segments.get(2).unwrap()),
-                );
+                line.expect_operands(2)?;
+                return Ok(Operator::JMP_DEF(
+                    line.string_operand(1)?,
+                    line.usize_operand(2)?,
+                ));
             }
             "LABEL" => {
-                return Operator::LABEL(clean_string(segments.get(1).unwrap().clone()));
+                line.expect_operands(1)?;
+                return Ok(Operator::LABEL(line.string_operand(1)?));
             }
             "SYSCALL" => {
-                return Operator::SYSCALL(
-                    str_to_usize(&*segments.get(1).unwrap()),
-                    str_to_usize(&*segments.get(2).unwrap()),
-                );
+                line.expect_operands(2)?;
+                return Ok(Operator::SYSCALL(
+                    line.usize_operand(1)?,
+                    line.usize_operand(2)?,
+                ));
             }
             "SYSCALLD" => {
-                return Operator::SYSCALLD(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::SYSCALLD(line.usize_operand(1)?));
             }
             "EXCEPT_THROW" => {
-                return Operator::EXCEPT_THROW;
+                return Ok(Operator::EXCEPT_THROW);
             }
             "EXCEPT_CATCH" => {
-                return Operator::EXCEPT_CATCH(clean_string(segments.get(1).unwrap().clone()));
+                line.expect_operands(1)?;
+                return Ok(Operator::EXCEPT_CATCH(line.string_operand(1)?));
             }
             "RET" => {
-                return Operator::RET;
+                return Ok(Operator::RET);
             }
             "EMIT" => {
-                return Operator::EMIT;
+                return Ok(Operator::EMIT);
             }
             "EMITS" => {
-                return Operator::EMITS(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::EMITS(line.usize_operand(1)?));
             }
             "EMITW" => {
-                return Operator::EMITW(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::EMITW(line.usize_operand(1)?));
             }
             "EMITD" => {
-                return Operator::EMITD(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::EMITD(line.usize_operand(1)?));
             }
             "DJMP" => {
-                return Operator::DJMP;
+                return Ok(Operator::DJMP);
             }
             "DJMPe" => {
-                return Operator::DJMPe;
+                return Ok(Operator::DJMPe);
             }
             "DJMPne" => {
-                return Operator::DJMPne;
+                return Ok(Operator::DJMPne);
             }
             "DALLOC" => {
-                return Operator::DALLOC(str_to_usize(&*segments.get(1).unwrap()));
+                line.expect_operands(1)?;
+                return Ok(Operator::DALLOC(line.usize_operand(1)?));
             }
             "LIBLOAD" => {
-                return Operator::LIBLOAD(clean_string(segments.get(1).unwrap().clone()));
+                line.expect_operands(1)?;
+                return Ok(Operator::LIBLOAD(line.string_operand(1)?));
             }
             "DLIBLOAD" => {
-                return Operator::DLIBLOAD;
+                return Ok(Operator::DLIBLOAD);
             }
             "LIBCALL" => {
-                return Operator::LIBCALL(
-                    clean_string(segments.get(1).unwrap().clone()),
-                    clean_string(segments.get(2).unwrap().clone()),
-                );
+                line.expect_operands(2)?;
+                return Ok(Operator::LIBCALL(
+                    line.string_operand(1)?,
+                    line.string_operand(2)?,
+                ));
             }
             "DLIBCALL" => {
-                return Operator::DLIBCALL(clean_string(segments.get(1).unwrap().clone()));
+                line.expect_operands(1)?;
+                return Ok(Operator::DLIBCALL(line.string_operand(1)?));
             }
             "LIBDCALL" => {
-                return Operator::LIBDCALL(clean_string(segments.get(1).unwrap().clone()));
+                line.expect_operands(1)?;
+                return Ok(Operator::LIBDCALL(line.string_operand(1)?));
             }
             "DLIBDCALL" => {
-                return Operator::DLIBDCALL;
+                return Ok(Operator::DLIBDCALL);
+            }
+            "DNEW" => return Ok(Operator::DNEW),
+            "DGETSIZE" => return Ok(Operator::DGETSIZE),
+            "DLOADVALUE" => return Ok(Operator::DLOADVALUE),
+            "DDEALLOC" => return Ok(Operator::DDEALLOC),
+            "DGETWORD" => return Ok(Operator::DGETWORD),
+            "DSETWORD" => return Ok(Operator::DSETWORD),
+            "DGETBYTE" => return Ok(Operator::DGETBYTE),
+            "DSETBYTE" => return Ok(Operator::DSETBYTE),
+            "CALL" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::CALL(line.string_operand(1)?));
+            }
+            "CALLD" => return Ok(Operator::CALLD),
+            "BR" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::BR(line.string_operand(1)?));
+            }
+            "BRo" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::BRo(line.string_operand(1)?));
+            }
+            "BRe" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::BRe(line.string_operand(1)?));
+            }
+            "BRne" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::BRne(line.string_operand(1)?));
+            }
+            "BRs" => {
+                line.expect_operands(2)?;
+                return Ok(Operator::BRs(
+                    line.string_operand(1)?,
+                    line.string_operand(2)?,
+                ));
+            }
+            "DBR" => return Ok(Operator::DBR),
+            "DBRe" => return Ok(Operator::DBRe),
+            "DBRne" => return Ok(Operator::DBRne),
+            "EXCEPT_THROWC" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::EXCEPT_THROWC(line.u32_operand(1)?));
+            }
+            "EXCEPT_THROWD" => return Ok(Operator::EXCEPT_THROWD),
+            "EXCEPT_THROWP" => {
+                line.expect_operands(2)?;
+                return Ok(Operator::EXCEPT_THROWP(
+                    line.u32_operand(1)?,
+                    line.usize_operand(2)?,
+                ));
+            }
+            "EXCEPT_CATCH_RANGE" => {
+                line.expect_operands(3)?;
+                return Ok(Operator::EXCEPT_CATCH_RANGE(
+                    line.string_operand(1)?,
+                    line.u32_operand(2)?,
+                    line.u32_operand(3)?,
+                ));
+            }
+            "EXCEPT_FINALLY" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::EXCEPT_FINALLY(line.string_operand(1)?));
+            }
+            "EXCEPT_END" => return Ok(Operator::EXCEPT_END),
+            "EXCEPT_CODE" => return Ok(Operator::EXCEPT_CODE),
+            "EXCEPT_PAYLOAD" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::EXCEPT_PAYLOAD(line.usize_operand(1)?));
+            }
+            "CMPu" => return Ok(Operator::CMPu),
+            "CMPi" => return Ok(Operator::CMPi),
+            "CMPf" => return Ok(Operator::CMPf),
+            "JMPlt" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::JMPlt(line.string_operand(1)?));
+            }
+            "JMPgt" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::JMPgt(line.string_operand(1)?));
+            }
+            "JMPle" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::JMPle(line.string_operand(1)?));
+            }
+            "JMPge" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::JMPge(line.string_operand(1)?));
+            }
+            "BRlt" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::BRlt(line.string_operand(1)?));
+            }
+            "BRgt" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::BRgt(line.string_operand(1)?));
+            }
+            "BRle" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::BRle(line.string_operand(1)?));
+            }
+            "BRge" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::BRge(line.string_operand(1)?));
+            }
+            "SETREG64" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::SETREG64(line.usize_operand(1)?));
+            }
+            "GETREG64" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::GETREG64(line.usize_operand(1)?));
+            }
+            "SETREG128" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::SETREG128(line.usize_operand(1)?));
+            }
+            "GETREG128" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::GETREG128(line.usize_operand(1)?));
+            }
+            "ADD64u" => return Ok(Operator::ADD64u),
+            "ADD64i" => return Ok(Operator::ADD64i),
+            "SUB64u" => return Ok(Operator::SUB64u),
+            "SUB64i" => return Ok(Operator::SUB64i),
+            "MUL64u" => return Ok(Operator::MUL64u),
+            "MUL64i" => return Ok(Operator::MUL64i),
+            "DIV64u" => return Ok(Operator::DIV64u),
+            "DIV64i" => return Ok(Operator::DIV64i),
+            "LSL64" => return Ok(Operator::LSL64),
+            "LSR64" => return Ok(Operator::LSR64),
+            "ASR64" => return Ok(Operator::ASR64),
+            "CMP64u" => return Ok(Operator::CMP64u),
+            "CMP64i" => return Ok(Operator::CMP64i),
+            "ADD128u" => return Ok(Operator::ADD128u),
+            "SUB128u" => return Ok(Operator::SUB128u),
+            "MUL128u" => return Ok(Operator::MUL128u),
+            "DIV128u" => return Ok(Operator::DIV128u),
+            "LSL128" => return Ok(Operator::LSL128),
+            "LSR128" => return Ok(Operator::LSR128),
+            "CMP128u" => return Ok(Operator::CMP128u),
+            "LOADf" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::LOADf(line.usize_operand(1)?));
+            }
+            "LOADi" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::LOADi(line.usize_operand(1)?));
+            }
+            "ITOF" => return Ok(Operator::ITOF),
+            "FTOI" => return Ok(Operator::FTOI),
+            "UTOF" => return Ok(Operator::UTOF),
+            "SQRTf" => return Ok(Operator::SQRTf),
+            "ABSf" => return Ok(Operator::ABSf),
+            "FLOORf" => return Ok(Operator::FLOORf),
+            "CEILf" => return Ok(Operator::CEILf),
+            "MINf" => return Ok(Operator::MINf),
+            "MAXf" => return Ok(Operator::MAXf),
+            "POWf" => return Ok(Operator::POWf),
+            "RORC" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::RORC(line.u32_operand(1)?));
+            }
+            "ROLC" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::ROLC(line.u32_operand(1)?));
+            }
+            "LSRC" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::LSRC(line.u32_operand(1)?));
+            }
+            "ASRC" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::ASRC(line.u32_operand(1)?));
+            }
+            "LSLC" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::LSLC(line.u32_operand(1)?));
+            }
+            "ASLC" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::ASLC(line.u32_operand(1)?));
+            }
+            "RORD" => return Ok(Operator::RORD),
+            "ROLD" => return Ok(Operator::ROLD),
+            "LSRD" => return Ok(Operator::LSRD),
+            "ASRD" => return Ok(Operator::ASRD),
+            "LSLD" => return Ok(Operator::LSLD),
+            "ASLD" => return Ok(Operator::ASLD),
+            "CLZ" => return Ok(Operator::CLZ),
+            "CTZ" => return Ok(Operator::CTZ),
+            "OVER" => return Ok(Operator::OVER),
+            "ROT" => return Ok(Operator::ROT),
+            "PICK" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::PICK(line.usize_operand(1)?));
+            }
+            "ROLL" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::ROLL(line.usize_operand(1)?));
+            }
+            "DROP" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::DROP(line.usize_operand(1)?));
+            }
+            "DEPTH" => return Ok(Operator::DEPTH),
+            "CLEAR" => return Ok(Operator::CLEAR),
+            "ENTER" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::ENTER(line.usize_operand(1)?));
+            }
+            "LOCAL_GET" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::LOCAL_GET(line.usize_operand(1)?));
+            }
+            "LOCAL_SET" => {
+                line.expect_operands(1)?;
+                return Ok(Operator::LOCAL_SET(line.usize_operand(1)?));
             }
             &_ => {
                 if first_segment.starts_with(".") {
                     let v = first_segment.replace(".", "").to_string();
-                    return Operator::LABEL(clean_string(v));
+                    return Ok(Operator::LABEL(clean_string(v)));
                 } else {
-                    panic!("Invalid operation: {}", first_segment);
+                    return Err(line.diagnostic(
+                        &line.tokens[0],
+                        format!("invalid operation '{}'", first_segment),
+                        "unknown operation".to_string(),
+                    ));
                 }
             }
         }
     }
 
-    fn get_segments_from_line(line: String) -> Vec<String> {
-        let trimmed_line = line.trim();
-
-        let mut segments: Vec<String> = vec![];
-
-        let all_chars = trimmed_line.chars().collect::<Vec<char>>();
-
-        let mut i = 0;
-        let mut current_segment = String::new();
-        while i < all_chars.len() {
-            let current_char = all_chars[i];
-            if current_char == ' ' || current_char == '\t' {
-                if current_segment.len() > 0 {
-                    segments.push(current_segment);
-                    current_segment = String::new();
-                }
-            } else if current_char == '"' {
-                //consume string and add it to line
-                let mut string_builder = String::new();
-                i += 1;
-                string_builder.push(current_char);
-                let mut next = all_chars[i];
-                while next != '"' || (next == '"' && all_chars[i - 1] == '\\') {
-                    string_builder.push(next);
-                    i += 1;
-                    next = all_chars[i];
-                }
-
-                string_builder.push(next);
-                let mut v = string_builder.to_string();
-                v = v.replace("\\n", "\n");
-                v = v.replace("\\r", "\r");
-                i += 1;
-                current_segment.push_str(&*v);
-                //finished quoted string, finish segments and start new
-                segments.push(current_segment);
-                current_segment = String::new();
-            } else {
-                current_segment.push(current_char);
-            }
-            i += 1;
-        }
-
-        if current_segment.len() > 0 {
-            segments.push(current_segment);
-        }
-
-        return segments.iter().map(|s| s.to_string()).collect();
-
-        //split on whitespaces but keep whitespace in string
-    }
-
-    fn str_to_usize(s: &str) -> usize {
+    pub(crate) fn str_to_usize(s: &str) -> Option<usize> {
         //match first 2 starting chars for 0b 0r 0x and convert to usize, otherwise treat as decimal
         let mut s = s.to_string();
         s = s.replace("_", "");
@@ -444,19 +907,19 @@ pub mod asm_parser {
         }
     }
 
-    fn hex_to_usize(hex: String) -> usize {
-        return usize::from_str_radix(&*hex, 16).unwrap();
+    fn hex_to_usize(hex: String) -> Option<usize> {
+        return usize::from_str_radix(&*hex, 16).ok();
     }
 
-    fn bin_to_usize(bin: String) -> usize {
-        return usize::from_str_radix(&*bin, 2).unwrap();
+    fn bin_to_usize(bin: String) -> Option<usize> {
+        return usize::from_str_radix(&*bin, 2).ok();
     }
 
-    fn dec_to_usize(dec: String) -> usize {
-        return usize::from_str_radix(&*dec, 10).unwrap();
+    fn dec_to_usize(dec: String) -> Option<usize> {
+        return usize::from_str_radix(&*dec, 10).ok();
     }
 
-    fn str_to_u32(s: &str) -> u32 {
+    pub(crate) fn str_to_u32(s: &str) -> Option<u32> {
         //match first 2 starting chars for 0b 0r 0x and convert to usize, otherwise treat as decimal
         let mut s = s.to_string();
         s = s.replace("_", "");
@@ -474,19 +937,19 @@ pub mod asm_parser {
         }
     }
 
-    fn hex_to_u32(hex: String) -> u32 {
-        return u32::from_str_radix(&*hex, 16).unwrap();
+    fn hex_to_u32(hex: String) -> Option<u32> {
+        return u32::from_str_radix(&*hex, 16).ok();
     }
 
-    fn bin_to_u32(bin: String) -> u32 {
-        return u32::from_str_radix(&*bin, 2).unwrap();
+    fn bin_to_u32(bin: String) -> Option<u32> {
+        return u32::from_str_radix(&*bin, 2).ok();
     }
 
-    fn dec_to_u32(dec: String) -> u32 {
-        return u32::from_str_radix(&*dec, 10).unwrap();
+    fn dec_to_u32(dec: String) -> Option<u32> {
+        return u32::from_str_radix(&*dec, 10).ok();
     }
 
-    fn str_to_u8(s: &str) -> u8 {
+    fn str_to_u8(s: &str) -> Option<u8> {
         //match first 2 starting chars for 0b 0r 0x and convert to usize, otherwise treat as decimal
         let mut s = s.to_string();
         s = s.replace("_", "");
@@ -504,19 +967,19 @@ pub mod asm_parser {
         }
     }
 
-    fn hex_to_u8(hex: String) -> u8 {
-        return u8::from_str_radix(&*hex, 16).unwrap();
+    fn hex_to_u8(hex: String) -> Option<u8> {
+        return u8::from_str_radix(&*hex, 16).ok();
     }
 
-    fn bin_to_u8(bin: String) -> u8 {
-        return u8::from_str_radix(&*bin, 2).unwrap();
+    fn bin_to_u8(bin: String) -> Option<u8> {
+        return u8::from_str_radix(&*bin, 2).ok();
     }
 
-    fn dec_to_u8(dec: String) -> u8 {
-        return u8::from_str_radix(&*dec, 10).unwrap();
+    fn dec_to_u8(dec: String) -> Option<u8> {
+        return u8::from_str_radix(&*dec, 10).ok();
     }
 
-    fn str_to_f32(s: &str) -> f32 {
+    fn str_to_f32(s: &str) -> Option<f32> {
         //match first 2 starting chars for 0b 0r 0x and convert to usize, otherwise treat as decimal
         let mut s = s.to_string();
         s = s.replace("_", "");
@@ -525,10 +988,10 @@ pub mod asm_parser {
             s.remove(s.len() - 1);
         };
 
-        f32::from_str(&*s).unwrap()
+        f32::from_str(&*s).ok()
     }
 
-    fn str_to_i32(s: &str) -> i32 {
+    fn str_to_i32(s: &str) -> Option<i32> {
         //match first 2 starting chars for 0b 0r 0x and convert to usize, otherwise treat as decimal
         let mut s = s.to_string();
         s = s.replace("_", "");
@@ -537,39 +1000,37 @@ pub mod asm_parser {
             s.remove(s.len() - 1);
         };
 
-        i32::from_str(&*s).unwrap()
+        i32::from_str(&*s).ok()
     }
 
-    fn str_to_bool(s: &str) -> bool {
+    fn str_to_bool(s: &str) -> Option<bool> {
         //match first 2 starting chars for 0b 0r 0x and convert to usize, otherwise treat as decimal
         let s = s.to_string();
         if s.len() == 1 {
             if s.to_lowercase() == "t" {
-                return true;
+                return Some(true);
             } else if s.to_lowercase() == "f" {
-                return false;
+                return Some(false);
             } else if s.to_lowercase() == "1" {
-                return true;
+                return Some(true);
             } else if s.to_lowercase() == "0" {
-                return false;
+                return Some(false);
             }
         }
 
         if s.to_lowercase() == "true" {
-            return true;
+            return Some(true);
         } else if s.to_lowercase() == "false" {
-            return false;
+            return Some(false);
         }
 
-        panic!(
-            "Could not convert {} to bool. valid formats are 0,1,t,f,true,false",
-            s
-        );
+        None
     }
 
     fn clean_string(s: String) -> String {
         let mut s = s.to_string();
-        if s.starts_with("\"") {
+        // an unterminated string may be a lone quote, it is reported by tokenize
+        if s.len() >= 2 && s.starts_with("\"") && s.ends_with("\"") {
             //remove from start and end
             s.remove(0);
             s.remove(s.len() - 1);
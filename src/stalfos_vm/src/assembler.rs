@@ -2,8 +2,80 @@ pub mod assembler {
     use crate::ops::ops::Operator;
     use std::fs::File;
     use std::io::Write;
+    use std::fmt;
     use std::mem;
 
+    /// what the decoder was trying to read when it failed
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum OperandKind {
+        Magic,
+        Opcode,
+        Version,
+        Usize,
+        U32,
+        F32,
+        I32,
+        U8,
+        Bool,
+        String,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum DecodeErrorKind {
+        // the binary ended part way through an operand
+        UnexpectedEnd,
+        // the byte at offset is not a known opcode
+        UnknownOpcode,
+        // the bytes were read but are not valid for the operand, eg a bool that is not 0x00 or 0xff
+        InvalidValue,
+        // the binary has a version marker for a format version this build cannot read
+        UnsupportedVersion,
+    }
+
+    ///
+    /// A .stf or .stalib binary that could not be decoded.
+    /// offset is the byte offset into the binary, opcode is the opcode being decoded (None for the header)
+    ///
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct DecodeError {
+        pub offset: usize,
+        pub expected: OperandKind,
+        pub opcode: Option<u8>,
+        pub kind: DecodeErrorKind,
+    }
+
+    impl fmt::Display for DecodeError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let problem = match self.kind {
+                DecodeErrorKind::UnexpectedEnd => "unexpected end of binary",
+                DecodeErrorKind::UnknownOpcode => "unknown opcode",
+                DecodeErrorKind::InvalidValue => "invalid value",
+                DecodeErrorKind::UnsupportedVersion => "unsupported format version",
+            };
+            write!(f, "{} at byte {}: expected {:?}", problem, self.offset, self.expected)?;
+            if let Some(opcode) = self.opcode {
+                write!(f, " while decoding opcode 0x{:02X}", opcode)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl std::error::Error for DecodeError {}
+
+    ///
+    /// Binaries start with the magic bytes (0xDEADFACE for programs, 0xDEADC0DE for libraries)
+    /// followed by VERSION_MARKER and the FORMAT_VERSION they were written with.
+    ///
+    /// version 1: every usize operand and string length prefix is a big-endian u64
+    ///
+    /// Binaries without the marker are the original layout, where usize operands are as wide as
+    /// the usize of the machine that assembled them. These are still read as the host's usize.
+    /// The marker can never be confused with the old layout: it is not an opcode, and it would be
+    /// the first byte of an impossibly long library namespace length.
+    ///
+    pub const FORMAT_VERSION: u8 = 1;
+    pub const VERSION_MARKER: u8 = 0xFF;
+
     pub fn assemble(program: &Vec<Operator>, library_ns: String) -> Vec<u8> {
         let mut val: Vec<u8> = Vec::new();
 
@@ -13,13 +85,16 @@ pub mod assembler {
 
         if library_ns.len() > 0 {
             val.extend_from_slice(&library_bytes);
-            let ns_length = library_ns.len();
-            let ns_len_bytes = ns_length.to_be_bytes();
-            val.extend_from_slice(&ns_len_bytes);
-            val.extend_from_slice(library_ns.as_bytes());
         } else {
             val.extend_from_slice(&magic_bytes);
         }
+
+        val.push(VERSION_MARKER);
+        val.push(FORMAT_VERSION);
+
+        if library_ns.len() > 0 {
+            val.extend_from_slice(&*str_op_value_bytes(&library_ns));
+        }
         for operation in program {
             val.extend(get_operation_bytes(operation))
         }
@@ -39,40 +114,40 @@ pub mod assembler {
             /*opcode : 2*/
             Operator::LOAD(v) => {
                 let mut op_bytes: Vec<u8> = vec![0x02];
-                op_bytes.extend_from_slice(&v.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode : 3*/
             Operator::LOADD(v_) => {
                 let mut op_bytes: Vec<u8> = vec![0x03];
-                op_bytes.extend_from_slice(&v_.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v_));
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode : 4*/
             Operator::CONST_U(v1, v2) => {
                 let mut op_bytes: Vec<u8> = vec![0x04];
-                op_bytes.extend_from_slice(&v1.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
                 op_bytes.extend_from_slice(&v2.to_be_bytes());
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode : 5*/
             Operator::CONST_F(v1, v2) => {
                 let mut op_bytes: Vec<u8> = vec![0x05];
-                op_bytes.extend_from_slice(&v1.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
                 op_bytes.extend_from_slice(&v2.to_be_bytes());
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode : 6*/
             Operator::CONST_I(v1, v2) => {
                 let mut op_bytes: Vec<u8> = vec![0x06];
-                op_bytes.extend_from_slice(&v1.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
                 op_bytes.extend_from_slice(&v2.to_be_bytes());
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode : 7*/
             Operator::CONST_B(v1, v2) => {
                 let mut op_bytes: Vec<u8> = vec![0x07];
-                op_bytes.extend_from_slice(&v1.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
                 let _v2 = if *v2 { 0xff } else { 0x00 };
                 op_bytes.push(_v2);
                 val.extend_from_slice(&op_bytes);
@@ -80,7 +155,7 @@ pub mod assembler {
             /*opcode : 8*/
             Operator::CONST_S(v1, v2) => {
                 let mut op_bytes: Vec<u8> = vec![0x08];
-                op_bytes.extend_from_slice(&v1.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
                 let sbytes = &*str_op_value_bytes(&v2);
                 op_bytes.extend_from_slice(sbytes);
                 val.extend_from_slice(&op_bytes);
@@ -89,7 +164,7 @@ pub mod assembler {
             Operator::LOAD_CONST(v) => {
                 //0x09
                 let mut op_bytes: Vec<u8> = vec![0x09];
-                op_bytes.extend_from_slice(&v.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :10*/
@@ -100,26 +175,26 @@ pub mod assembler {
             /*opcode :11*/
             Operator::ALLOC(v1, v2) => {
                 let mut op_bytes: Vec<u8> = vec![0x0B];
-                op_bytes.extend_from_slice(&v1.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
                 op_bytes.extend_from_slice(&v2.to_be_bytes());
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :12*/
             Operator::DEALLOC(v) => {
                 let mut op_bytes: Vec<u8> = vec![0x0C];
-                op_bytes.extend_from_slice(&v.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :13*/
             Operator::POPS(v) => {
                 let mut op_bytes: Vec<u8> = vec![0x0D];
-                op_bytes.extend_from_slice(&v.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :14*/
             Operator::GETLEN(v) => {
                 let mut op_bytes: Vec<u8> = vec![0x0E];
-                op_bytes.extend_from_slice(&v.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :15*/
@@ -158,7 +233,7 @@ pub mod assembler {
             Operator::JMP_DEF(v1, v2) => {
                 let mut op_bytes: Vec<u8> = vec![0x14];
                 op_bytes.extend_from_slice(&*str_op_value_bytes(&v1));
-                op_bytes.extend_from_slice(&v2.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v2));
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :21*/
@@ -172,15 +247,15 @@ pub mod assembler {
             /*opcode :22*/
             Operator::SYSCALL(v1, v2) => {
                 let mut op_bytes: Vec<u8> = vec![0x16];
-                op_bytes.extend_from_slice(&v1.to_be_bytes());
-                op_bytes.extend_from_slice(&v2.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
+                op_bytes.extend_from_slice(&usize_to_bytes(*v2));
 
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :23*/
             Operator::SYSCALLD(v) => {
                 let mut op_bytes: Vec<u8> = vec![0x17];
-                op_bytes.extend_from_slice(&v.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
 
                 val.extend_from_slice(&op_bytes);
             }
@@ -206,50 +281,52 @@ pub mod assembler {
             /*opcode :28*/
             Operator::EMITS(v) => {
                 let mut op_bytes: Vec<u8> = vec![0x1C];
-                op_bytes.extend_from_slice(&v.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
 
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :29*/
             Operator::EMITW(v) => {
                 let mut op_bytes: Vec<u8> = vec![0x1D];
-                op_bytes.extend_from_slice(&v.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
 
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :30*/
             Operator::EMITD(v) => {
                 let mut op_bytes: Vec<u8> = vec![0x1E];
-                op_bytes.extend_from_slice(&v.to_be_bytes())
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+
+                val.extend_from_slice(&op_bytes);
             }
             /*opcode :31*/
             Operator::GETBYTELEN(v) => {
                 let mut op_bytes: Vec<u8> = vec![0x1F];
-                op_bytes.extend_from_slice(&v.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
 
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :32*/
             Operator::GETBYTE(v1, v2) => {
                 let mut op_bytes: Vec<u8> = vec![0x20];
-                op_bytes.extend_from_slice(&v1.to_be_bytes());
-                op_bytes.extend_from_slice(&v2.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
+                op_bytes.extend_from_slice(&usize_to_bytes(*v2));
 
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :33*/
             Operator::GETWORD(v1, v2) => {
                 let mut op_bytes: Vec<u8> = vec![0x21];
-                op_bytes.extend_from_slice(&v1.to_be_bytes());
-                op_bytes.extend_from_slice(&v2.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
+                op_bytes.extend_from_slice(&usize_to_bytes(*v2));
 
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :34*/
             Operator::SETBYTE(v1, v2, v3) => {
                 let mut op_bytes: Vec<u8> = vec![0x22];
-                op_bytes.extend_from_slice(&v1.to_be_bytes());
-                op_bytes.extend_from_slice(&v2.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
+                op_bytes.extend_from_slice(&usize_to_bytes(*v2));
                 op_bytes.push(*v3);
 
                 val.extend_from_slice(&op_bytes);
@@ -257,8 +334,8 @@ pub mod assembler {
             /*opcode :35*/
             Operator::SETWORD(v1, v2, v3) => {
                 let mut op_bytes: Vec<u8> = vec![0x23];
-                op_bytes.extend_from_slice(&v1.to_be_bytes());
-                op_bytes.extend_from_slice(&v2.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
+                op_bytes.extend_from_slice(&usize_to_bytes(*v2));
                 op_bytes.extend_from_slice(&v3.to_be_bytes());
 
                 val.extend_from_slice(&op_bytes);
@@ -266,7 +343,7 @@ pub mod assembler {
             /*opcode :36*/
             Operator::DUPO(v) => {
                 let mut op_bytes: Vec<u8> = vec![0x24];
-                op_bytes.extend_from_slice(&v.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
                 val.extend_from_slice(&op_bytes);
             }
             /*opcode :37*/ Operator::DUP => val.push(0x25),
@@ -327,7 +404,7 @@ pub mod assembler {
             /* opcode: 82*/
             Operator::DALLOC(v) => {
                 let mut op_bytes: Vec<u8> = vec![0x52];
-                op_bytes.extend_from_slice(&v.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
 
                 val.extend_from_slice(&op_bytes);
             }
@@ -365,6 +442,479 @@ pub mod assembler {
             Operator::DLIBDCALL => {
                 val.push(0x58);
             }
+            /* opcode: 89*/
+            Operator::DNEW => {
+                val.push(0x59);
+            }
+            /* opcode: 90*/
+            Operator::DGETSIZE => {
+                val.push(0x5A);
+            }
+            /* opcode: 91*/
+            Operator::DLOADVALUE => {
+                val.push(0x5B);
+            }
+            /* opcode: 92*/
+            Operator::DDEALLOC => {
+                val.push(0x5C);
+            }
+            /* opcode: 93*/
+            Operator::DGETWORD => {
+                val.push(0x5D);
+            }
+            /* opcode: 94*/
+            Operator::DSETWORD => {
+                val.push(0x5E);
+            }
+            /* opcode: 95*/
+            Operator::DGETBYTE => {
+                val.push(0x5F);
+            }
+            /* opcode: 96*/
+            Operator::DSETBYTE => {
+                val.push(0x60);
+            }
+            /* opcode: 97*/
+            Operator::ALLOC_PERSIST(v1, v2) => {
+                let mut op_bytes: Vec<u8> = vec![0x61];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v1));
+                op_bytes.extend_from_slice(&v2.to_be_bytes());
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 98*/
+            Operator::CALL(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x62];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 99*/
+            Operator::CALLD => {
+                val.push(0x63);
+            }
+            /* opcode: 100*/
+            Operator::BR(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x64];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 101*/
+            Operator::BRo(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x65];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 102*/
+            Operator::BRe(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x66];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 103*/
+            Operator::BRne(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x67];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 104*/
+            Operator::BRs(v1, v2) => {
+                let mut op_bytes: Vec<u8> = vec![0x68];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v1));
+                op_bytes.extend_from_slice(&str_op_value_bytes(v2));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 105*/
+            Operator::DBR => {
+                val.push(0x69);
+            }
+            /* opcode: 106*/
+            Operator::DBRe => {
+                val.push(0x6A);
+            }
+            /* opcode: 107*/
+            Operator::DBRne => {
+                val.push(0x6B);
+            }
+            /* opcode: 108*/
+            Operator::EXCEPT_THROWC(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x6C];
+                op_bytes.extend_from_slice(&v.to_be_bytes());
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 109*/
+            Operator::EXCEPT_THROWD => {
+                val.push(0x6D);
+            }
+            /* opcode: 110*/
+            Operator::EXCEPT_THROWP(v1, v2) => {
+                let mut op_bytes: Vec<u8> = vec![0x6E];
+                op_bytes.extend_from_slice(&v1.to_be_bytes());
+                op_bytes.extend_from_slice(&usize_to_bytes(*v2));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 111*/
+            Operator::EXCEPT_CATCH_RANGE(v1, v2, v3) => {
+                let mut op_bytes: Vec<u8> = vec![0x6F];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v1));
+                op_bytes.extend_from_slice(&v2.to_be_bytes());
+                op_bytes.extend_from_slice(&v3.to_be_bytes());
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 112*/
+            Operator::EXCEPT_FINALLY(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x70];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 113*/
+            Operator::EXCEPT_END => {
+                val.push(0x71);
+            }
+            /* opcode: 114*/
+            Operator::EXCEPT_CODE => {
+                val.push(0x72);
+            }
+            /* opcode: 115*/
+            Operator::EXCEPT_PAYLOAD(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x73];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 116*/
+            Operator::CMPu => {
+                val.push(0x74);
+            }
+            /* opcode: 117*/
+            Operator::CMPi => {
+                val.push(0x75);
+            }
+            /* opcode: 118*/
+            Operator::CMPf => {
+                val.push(0x76);
+            }
+            /* opcode: 119*/
+            Operator::JMPlt(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x77];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 120*/
+            Operator::JMPgt(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x78];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 121*/
+            Operator::JMPle(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x79];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 122*/
+            Operator::JMPge(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x7A];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 123*/
+            Operator::BRlt(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x7B];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 124*/
+            Operator::BRgt(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x7C];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 125*/
+            Operator::BRle(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x7D];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 126*/
+            Operator::BRge(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x7E];
+                op_bytes.extend_from_slice(&str_op_value_bytes(v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 127*/
+            Operator::SETREG64(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x7F];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 128*/
+            Operator::GETREG64(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x80];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 129*/
+            Operator::SETREG128(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x81];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 130*/
+            Operator::GETREG128(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x82];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 131*/
+            Operator::ADD64u => {
+                val.push(0x83);
+            }
+            /* opcode: 132*/
+            Operator::ADD64i => {
+                val.push(0x84);
+            }
+            /* opcode: 133*/
+            Operator::SUB64u => {
+                val.push(0x85);
+            }
+            /* opcode: 134*/
+            Operator::SUB64i => {
+                val.push(0x86);
+            }
+            /* opcode: 135*/
+            Operator::MUL64u => {
+                val.push(0x87);
+            }
+            /* opcode: 136*/
+            Operator::MUL64i => {
+                val.push(0x88);
+            }
+            /* opcode: 137*/
+            Operator::DIV64u => {
+                val.push(0x89);
+            }
+            /* opcode: 138*/
+            Operator::DIV64i => {
+                val.push(0x8A);
+            }
+            /* opcode: 139*/
+            Operator::LSL64 => {
+                val.push(0x8B);
+            }
+            /* opcode: 140*/
+            Operator::LSR64 => {
+                val.push(0x8C);
+            }
+            /* opcode: 141*/
+            Operator::ASR64 => {
+                val.push(0x8D);
+            }
+            /* opcode: 142*/
+            Operator::CMP64u => {
+                val.push(0x8E);
+            }
+            /* opcode: 143*/
+            Operator::CMP64i => {
+                val.push(0x8F);
+            }
+            /* opcode: 144*/
+            Operator::ADD128u => {
+                val.push(0x90);
+            }
+            /* opcode: 145*/
+            Operator::SUB128u => {
+                val.push(0x91);
+            }
+            /* opcode: 146*/
+            Operator::MUL128u => {
+                val.push(0x92);
+            }
+            /* opcode: 147*/
+            Operator::DIV128u => {
+                val.push(0x93);
+            }
+            /* opcode: 148*/
+            Operator::LSL128 => {
+                val.push(0x94);
+            }
+            /* opcode: 149*/
+            Operator::LSR128 => {
+                val.push(0x95);
+            }
+            /* opcode: 150*/
+            Operator::CMP128u => {
+                val.push(0x96);
+            }
+            /* opcode: 151*/
+            Operator::LOADf(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x97];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 152*/
+            Operator::LOADi(v) => {
+                let mut op_bytes: Vec<u8> = vec![0x98];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 153*/
+            Operator::ITOF => {
+                val.push(0x99);
+            }
+            /* opcode: 154*/
+            Operator::FTOI => {
+                val.push(0x9A);
+            }
+            /* opcode: 155*/
+            Operator::UTOF => {
+                val.push(0x9B);
+            }
+            /* opcode: 156*/
+            Operator::SQRTf => {
+                val.push(0x9C);
+            }
+            /* opcode: 157*/
+            Operator::ABSf => {
+                val.push(0x9D);
+            }
+            /* opcode: 158*/
+            Operator::FLOORf => {
+                val.push(0x9E);
+            }
+            /* opcode: 159*/
+            Operator::CEILf => {
+                val.push(0x9F);
+            }
+            /* opcode: 160*/
+            Operator::MINf => {
+                val.push(0xA0);
+            }
+            /* opcode: 161*/
+            Operator::MAXf => {
+                val.push(0xA1);
+            }
+            /* opcode: 162*/
+            Operator::POWf => {
+                val.push(0xA2);
+            }
+            /* opcode: 163*/
+            Operator::RORC(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xA3];
+                op_bytes.extend_from_slice(&v.to_be_bytes());
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 164*/
+            Operator::ROLC(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xA4];
+                op_bytes.extend_from_slice(&v.to_be_bytes());
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 165*/
+            Operator::LSRC(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xA5];
+                op_bytes.extend_from_slice(&v.to_be_bytes());
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 166*/
+            Operator::ASRC(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xA6];
+                op_bytes.extend_from_slice(&v.to_be_bytes());
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 167*/
+            Operator::LSLC(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xA7];
+                op_bytes.extend_from_slice(&v.to_be_bytes());
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 168*/
+            Operator::ASLC(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xA8];
+                op_bytes.extend_from_slice(&v.to_be_bytes());
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 169*/
+            Operator::RORD => {
+                val.push(0xA9);
+            }
+            /* opcode: 170*/
+            Operator::ROLD => {
+                val.push(0xAA);
+            }
+            /* opcode: 171*/
+            Operator::LSRD => {
+                val.push(0xAB);
+            }
+            /* opcode: 172*/
+            Operator::ASRD => {
+                val.push(0xAC);
+            }
+            /* opcode: 173*/
+            Operator::LSLD => {
+                val.push(0xAD);
+            }
+            /* opcode: 174*/
+            Operator::ASLD => {
+                val.push(0xAE);
+            }
+            /* opcode: 175*/
+            Operator::CLZ => {
+                val.push(0xAF);
+            }
+            /* opcode: 176*/
+            Operator::CTZ => {
+                val.push(0xB0);
+            }
+            /* opcode: 177*/
+            Operator::OVER => {
+                val.push(0xB1);
+            }
+            /* opcode: 178*/
+            Operator::ROT => {
+                val.push(0xB2);
+            }
+            /* opcode: 179*/
+            Operator::PICK(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xB3];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 180*/
+            Operator::ROLL(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xB4];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 181*/
+            Operator::DROP(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xB5];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 182*/
+            Operator::DEPTH => {
+                val.push(0xB6);
+            }
+            /* opcode: 183*/
+            Operator::CLEAR => {
+                val.push(0xB7);
+            }
+            /* opcode: 184*/
+            Operator::ENTER(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xB8];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 185*/
+            Operator::LOCAL_GET(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xB9];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
+            /* opcode: 186*/
+            Operator::LOCAL_SET(v) => {
+                let mut op_bytes: Vec<u8> = vec![0xBA];
+                op_bytes.extend_from_slice(&usize_to_bytes(*v));
+                val.extend_from_slice(&op_bytes);
+            }
         }
 
         val
@@ -374,13 +924,18 @@ pub mod assembler {
         let mut val_bytes: Vec<u8> = Vec::new();
         let string_bytes = val.as_bytes();
         let n_bytes = string_bytes.len();
-        let n_bytes_bytes = n_bytes.to_be_bytes();
+        let n_bytes_bytes = usize_to_bytes(n_bytes);
         val_bytes.extend_from_slice(&n_bytes_bytes);
         val_bytes.extend_from_slice(&string_bytes);
 
         return val_bytes;
     }
 
+    // usize operands are always written as 8 bytes so the binary does not depend on the host's pointer width
+    fn usize_to_bytes(v: usize) -> [u8; 8] {
+        (v as u64).to_be_bytes()
+    }
+
     pub fn write_to_file(bytes: &Vec<u8>, file_path: &str) {
         //write the program_binary to a file
         let mut file = File::create(file_path).unwrap();
@@ -388,7 +943,18 @@ pub mod assembler {
         file.flush().unwrap();
     }
 
+    ///
+    /// Decodes a .stf or .stalib binary, panicking if it is not valid.
+    /// Use try_parse_binary to report a corrupt file instead.
+    ///
     pub fn parse_binary(program_binary: Vec<u8>) -> (Vec<Operator>, String) {
+        match try_parse_binary(program_binary) {
+            Ok(result) => result,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    pub fn try_parse_binary(program_binary: Vec<u8>) -> Result<(Vec<Operator>, String), DecodeError> {
         let mut namespace: String = "".to_string();
         let mut operations: Vec<Operator> = Vec::new();
         let n_bytes = program_binary.len();
@@ -399,111 +965,122 @@ pub mod assembler {
         let is_library: bool;
 
         //check that the file starts with either magic or library bytes
+        if n_bytes < 4 {
+            return Err(DecodeError {
+                offset: n_bytes,
+                expected: OperandKind::Magic,
+                opcode: None,
+                kind: DecodeErrorKind::UnexpectedEnd,
+            });
+        }
 
         if program_binary[0..4] == magic_bytes {
             is_library = false;
         } else if program_binary[0..4] == library_bytes {
             is_library = true;
         } else {
-            panic!("File does not start with magic or library bytes");
+            return Err(DecodeError {
+                offset: 0,
+                expected: OperandKind::Magic,
+                opcode: None,
+                kind: DecodeErrorKind::InvalidValue,
+            });
         }
 
-        // for i in 0..4 {
-        //     if magic_bytes[i] != program_binary[i] {
-        //         panic!("magic_bytes not found at start of program_binary");
-        //     }
-        // }
-
-        let mut namespace_extracted = false;
+        // the read_next_* functions read the bytes after i, so i always points at the last byte consumed
+        let mut i = 3;
 
-        let mut i = 4;
-        for _ in 4..n_bytes - 1 {
-            if i >= n_bytes {
-                break;
+        // binaries without a version are the original layout with host sized usize operands
+        let mut usize_width = mem::size_of::<usize>();
+        if n_bytes > 4 && program_binary[4] == VERSION_MARKER {
+            let version = read_next_bytes(&program_binary, i + 1, 1, OperandKind::Version, None)?[0];
+            match version {
+                1 => usize_width = 8,
+                _ => {
+                    return Err(DecodeError {
+                        offset: 5,
+                        expected: OperandKind::Version,
+                        opcode: None,
+                        kind: DecodeErrorKind::UnsupportedVersion,
+                    })
+                }
             }
+            i += 2;
+        }
 
-            let byte = program_binary[i];
-
-            if is_library && !namespace_extracted {
-                // we are going back 1 byte because we aren't extracting it from an OPCODE
-                // usually we have opcode followed by operands,
-                // but this is a namespace string the exists before the first opcode
-                i -= 1;
-
-                let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
-                i += bytes_read;
+        if is_library {
+            // libraries have a namespace string before the first opcode
+            let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, None)?;
+            i += bytes_read;
 
-                let (string, bytes_read_2) = read_next_string(&program_binary, i, usize_value);
-                i += bytes_read_2;
+            let (string, bytes_read_2) = read_next_string(&program_binary, i, usize_value, None)?;
+            i += bytes_read_2;
 
-                namespace = string.clone();
+            namespace = string.clone();
+        }
 
-                //re-add the offset we removed because future OPCODEs will be offset by 1
-                i += 1;
-                namespace_extracted = true;
-                continue;
-            }
+        i += 1;
+        while i < n_bytes {
+            let byte = program_binary[i];
 
             match byte {
                 0x01 => {
                     //read next 4 bytes and treat them as a u32
-                    let mut u32_bytes: [u8; 4] = [0; 4];
-                    u32_bytes.copy_from_slice(&program_binary[i + 1..i + 5]);
-                    i += 4;
-                    let u32_val: u32 = u32::from_be_bytes(u32_bytes);
+                    let (u32_val, bytes_read) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read;
 
                     operations.push(Operator::PUSH(u32_val));
                 }
                 0x02 => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     operations.push(Operator::LOAD(usize_value));
                     i += bytes_read;
                 }
                 0x03 => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     operations.push(Operator::LOADD(usize_value));
                     i += bytes_read;
                 }
                 0x04 => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += bytes_read;
-                    let (u32_value, bytes_read_2) = read_next_u32(&program_binary, i);
+                    let (u32_value, bytes_read_2) = read_next_u32(&program_binary, i, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::CONST_U(usize_value, u32_value));
                 }
                 0x05 => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += bytes_read;
-                    let (f32_value, bytes_read_2) = read_next_f32(&program_binary, i);
+                    let (f32_value, bytes_read_2) = read_next_f32(&program_binary, i, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::CONST_F(usize_value, f32_value));
                 }
                 0x06 => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += bytes_read;
-                    let (i32_value, bytes_read_2) = read_next_i32(&program_binary, i);
+                    let (i32_value, bytes_read_2) = read_next_i32(&program_binary, i, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::CONST_I(usize_value, i32_value));
                 }
                 0x07 => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += bytes_read;
-                    let (bool, bytes_read_2) = read_next_bool(&program_binary, i);
+                    let (bool, bytes_read_2) = read_next_bool(&program_binary, i, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::CONST_B(usize_value, bool));
                 }
                 0x08 => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += bytes_read;
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (usize_value_2, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::CONST_S(usize_value, usize_value_2));
                 }
                 0x09 => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += bytes_read;
                     operations.push(Operator::LOAD_CONST(usize_value));
                 }
@@ -511,104 +1088,104 @@ pub mod assembler {
                     operations.push(Operator::POP);
                 }
                 0x0b => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += bytes_read;
-                    let (u32_value, bytes_read_2) = read_next_u32(&program_binary, i);
+                    let (u32_value, bytes_read_2) = read_next_u32(&program_binary, i, Some(byte))?;
                     i += bytes_read_2;
 
                     operations.push(Operator::ALLOC(usize_value, u32_value));
                 }
                 0x0c => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += bytes_read;
 
                     operations.push(Operator::DEALLOC(usize_value));
                 }
                 0x0d => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += bytes_read;
 
                     operations.push(Operator::POPS(usize_value));
                 }
                 0x0e => {
-                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i);
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += bytes_read;
 
                     operations.push(Operator::GETLEN(usize_value));
                 }
                 0x0f => {
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (string, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::JMP(string));
                 }
                 0x10 => {
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (string, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::JMPo(string));
                 }
                 0x11 => {
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (string, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::JMPe(string));
                 }
                 0x12 => {
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (string, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::JMPne(string));
                 }
                 0x13 => {
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (string, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
                     i += bytes_read_2;
-                    let (string_length2, str_len_read2) = read_next_usize(&program_binary, i);
+                    let (string_length2, str_len_read2) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read2;
                     let (string2, bytes_read_22) =
-                        read_next_string(&program_binary, i, string_length2);
+                        read_next_string(&program_binary, i, string_length2, Some(byte))?;
                     i += bytes_read_22;
                     operations.push(Operator::JMPs(string, string2));
                 }
                 0x14 => {
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (string, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
                     i += bytes_read_2;
-                    let (usize, usize_len) = read_next_usize(&program_binary, i);
+                    let (usize, usize_len) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += usize_len;
                     operations.push(Operator::JMP_DEF(string, usize));
                 }
                 0x15 => {
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (string, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
 
                     i += bytes_read_2;
                     operations.push(Operator::LABEL(string));
                 }
                 0x16 => {
-                    let (usize1, usize1bytes) = read_next_usize(&program_binary, i);
+                    let (usize1, usize1bytes) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += usize1bytes;
-                    let (usize2, usize2bytes) = read_next_usize(&program_binary, i);
+                    let (usize2, usize2bytes) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += usize2bytes;
                     operations.push(Operator::SYSCALL(usize1, usize2));
                 }
                 0x17 => {
-                    let (usize1, usize1bytes) = read_next_usize(&program_binary, i);
+                    let (usize1, usize1bytes) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += usize1bytes;
                     operations.push(Operator::SYSCALLD(usize1));
                 }
@@ -616,10 +1193,10 @@ pub mod assembler {
                     operations.push(Operator::EXCEPT_THROW);
                 }
                 0x19 => {
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (string, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::EXCEPT_CATCH(string));
                 }
@@ -630,60 +1207,60 @@ pub mod assembler {
                     operations.push(Operator::EMIT);
                 }
                 0x1C => {
-                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i);
+                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     operations.push(Operator::EMITS(usize_val));
                 }
                 0x1D => {
-                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i);
+                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     operations.push(Operator::EMITW(usize_val));
                 }
                 0x1E => {
-                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i);
+                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     operations.push(Operator::EMITD(usize_val));
                 }
                 0x1F => {
-                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i);
+                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     operations.push(Operator::GETBYTELEN(usize_val));
                 }
                 0x20 => {
-                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i);
+                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
-                    let (usize_val2, str_len_read2) = read_next_usize(&program_binary, i);
+                    let (usize_val2, str_len_read2) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read2;
                     operations.push(Operator::GETBYTE(usize_val, usize_val2));
                 }
                 0x21 => {
-                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i);
+                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
-                    let (usize_val2, str_len_read2) = read_next_usize(&program_binary, i);
+                    let (usize_val2, str_len_read2) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read2;
                     operations.push(Operator::GETWORD(usize_val, usize_val2));
                 }
                 0x22 => {
-                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i);
+                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
-                    let (usize_val2, str_len_read2) = read_next_usize(&program_binary, i);
+                    let (usize_val2, str_len_read2) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read2;
-                    i += 1;
-                    let next_byte = program_binary[i];
+                    let (next_byte, byte_length) = read_next_u8(&program_binary, i, Some(byte))?;
+                    i += byte_length;
 
                     operations.push(Operator::SETBYTE(usize_val, usize_val2, next_byte));
                 }
                 0x23 => {
-                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i);
+                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
-                    let (usize_val2, str_len_read2) = read_next_usize(&program_binary, i);
+                    let (usize_val2, str_len_read2) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read2;
-                    let (word, word_length) = read_next_u32(&program_binary, i);
+                    let (word, word_length) = read_next_u32(&program_binary, i, Some(byte))?;
                     i += word_length;
                     operations.push(Operator::SETWORD(usize_val, usize_val2, word));
                 }
                 0x24 => {
-                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i);
+                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     operations.push(Operator::DUPO(usize_val));
                 }
@@ -821,15 +1398,15 @@ pub mod assembler {
                     operations.push(Operator::DJMPne);
                 }
                 0x52 => {
-                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i);
+                    let (usize_val, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     operations.push(Operator::DALLOC(usize_val));
                 }
                 0x53 => {
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (string, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::LIBLOAD(string));
                 }
@@ -837,122 +1414,621 @@ pub mod assembler {
                     operations.push(Operator::DLIBLOAD);
                 }
                 0x55 => {
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (string, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
                     i += bytes_read_2;
 
-                    let (string_length2, str_len_read2) = read_next_usize(&program_binary, i);
+                    let (string_length2, str_len_read2) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read2;
                     let (string2, bytes_read_22) =
-                        read_next_string(&program_binary, i, string_length2);
+                        read_next_string(&program_binary, i, string_length2, Some(byte))?;
                     i += bytes_read_22;
                     operations.push(Operator::LIBCALL(string, string2));
                 }
                 0x56 => {
-                    let (string_length, str_len_read) = read_next_usize(&program_binary, i);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
                     i += str_len_read;
                     let (string, bytes_read_2) =
-                        read_next_string(&program_binary, i, string_length);
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
                     i += bytes_read_2;
                     operations.push(Operator::DLIBCALL(string));
                 }
                 0x57 => {
-                    operations.push(Operator::DLIBDCALL);
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read_2) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read_2;
+                    operations.push(Operator::LIBDCALL(string));
                 }
                 0x58 => {
                     operations.push(Operator::DLIBDCALL);
                 }
+                0x59 => {
+                    operations.push(Operator::DNEW);
+                }
+                0x5A => {
+                    operations.push(Operator::DGETSIZE);
+                }
+                0x5B => {
+                    operations.push(Operator::DLOADVALUE);
+                }
+                0x5C => {
+                    operations.push(Operator::DDEALLOC);
+                }
+                0x5D => {
+                    operations.push(Operator::DGETWORD);
+                }
+                0x5E => {
+                    operations.push(Operator::DSETWORD);
+                }
+                0x5F => {
+                    operations.push(Operator::DGETBYTE);
+                }
+                0x60 => {
+                    operations.push(Operator::DSETBYTE);
+                }
+                0x61 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    let (u32_value, bytes_read_2) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read_2;
+
+                    operations.push(Operator::ALLOC_PERSIST(usize_value, u32_value));
+                }
+                0x62 => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read_2) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read_2;
+                    operations.push(Operator::CALL(string));
+                }
+                0x63 => {
+                    operations.push(Operator::CALLD);
+                }
+                0x64 => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read_2) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read_2;
+                    operations.push(Operator::BR(string));
+                }
+                0x65 => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read_2) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read_2;
+                    operations.push(Operator::BRo(string));
+                }
+                0x66 => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read_2) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read_2;
+                    operations.push(Operator::BRe(string));
+                }
+                0x67 => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read_2) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read_2;
+                    operations.push(Operator::BRne(string));
+                }
+                0x68 => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read_2) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read_2;
+                    let (string_length2, str_len_read2) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read2;
+                    let (string2, bytes_read_22) =
+                        read_next_string(&program_binary, i, string_length2, Some(byte))?;
+                    i += bytes_read_22;
+                    operations.push(Operator::BRs(string, string2));
+                }
+                0x69 => {
+                    operations.push(Operator::DBR);
+                }
+                0x6A => {
+                    operations.push(Operator::DBRe);
+                }
+                0x6B => {
+                    operations.push(Operator::DBRne);
+                }
+                0x6C => {
+                    let (u32_value, bytes_read) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::EXCEPT_THROWC(u32_value));
+                }
+                0x6D => {
+                    operations.push(Operator::EXCEPT_THROWD);
+                }
+                0x6E => {
+                    let (u32_value, bytes_read) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read;
+                    let (usize_value, bytes_read_2) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read_2;
+                    operations.push(Operator::EXCEPT_THROWP(u32_value, usize_value));
+                }
+                0x6F => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read_2) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read_2;
+                    let (low, bytes_read_3) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read_3;
+                    let (high, bytes_read_4) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read_4;
+                    operations.push(Operator::EXCEPT_CATCH_RANGE(string, low, high));
+                }
+                0x70 => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read_2) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read_2;
+                    operations.push(Operator::EXCEPT_FINALLY(string));
+                }
+                0x71 => {
+                    operations.push(Operator::EXCEPT_END);
+                }
+                0x72 => {
+                    operations.push(Operator::EXCEPT_CODE);
+                }
+                0x73 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::EXCEPT_PAYLOAD(usize_value));
+                }
+                0x74 => {
+                    operations.push(Operator::CMPu);
+                }
+                0x75 => {
+                    operations.push(Operator::CMPi);
+                }
+                0x76 => {
+                    operations.push(Operator::CMPf);
+                }
+                0x77 => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::JMPlt(string));
+                }
+                0x78 => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::JMPgt(string));
+                }
+                0x79 => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::JMPle(string));
+                }
+                0x7A => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::JMPge(string));
+                }
+                0x7B => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::BRlt(string));
+                }
+                0x7C => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::BRgt(string));
+                }
+                0x7D => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::BRle(string));
+                }
+                0x7E => {
+                    let (string_length, str_len_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += str_len_read;
+                    let (string, bytes_read) =
+                        read_next_string(&program_binary, i, string_length, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::BRge(string));
+                }
+                0x7F => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::SETREG64(usize_value));
+                }
+                0x80 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::GETREG64(usize_value));
+                }
+                0x81 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::SETREG128(usize_value));
+                }
+                0x82 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::GETREG128(usize_value));
+                }
+                0x83 => {
+                    operations.push(Operator::ADD64u);
+                }
+                0x84 => {
+                    operations.push(Operator::ADD64i);
+                }
+                0x85 => {
+                    operations.push(Operator::SUB64u);
+                }
+                0x86 => {
+                    operations.push(Operator::SUB64i);
+                }
+                0x87 => {
+                    operations.push(Operator::MUL64u);
+                }
+                0x88 => {
+                    operations.push(Operator::MUL64i);
+                }
+                0x89 => {
+                    operations.push(Operator::DIV64u);
+                }
+                0x8A => {
+                    operations.push(Operator::DIV64i);
+                }
+                0x8B => {
+                    operations.push(Operator::LSL64);
+                }
+                0x8C => {
+                    operations.push(Operator::LSR64);
+                }
+                0x8D => {
+                    operations.push(Operator::ASR64);
+                }
+                0x8E => {
+                    operations.push(Operator::CMP64u);
+                }
+                0x8F => {
+                    operations.push(Operator::CMP64i);
+                }
+                0x90 => {
+                    operations.push(Operator::ADD128u);
+                }
+                0x91 => {
+                    operations.push(Operator::SUB128u);
+                }
+                0x92 => {
+                    operations.push(Operator::MUL128u);
+                }
+                0x93 => {
+                    operations.push(Operator::DIV128u);
+                }
+                0x94 => {
+                    operations.push(Operator::LSL128);
+                }
+                0x95 => {
+                    operations.push(Operator::LSR128);
+                }
+                0x96 => {
+                    operations.push(Operator::CMP128u);
+                }
+                0x97 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::LOADf(usize_value));
+                }
+                0x98 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::LOADi(usize_value));
+                }
+                0x99 => {
+                    operations.push(Operator::ITOF);
+                }
+                0x9A => {
+                    operations.push(Operator::FTOI);
+                }
+                0x9B => {
+                    operations.push(Operator::UTOF);
+                }
+                0x9C => {
+                    operations.push(Operator::SQRTf);
+                }
+                0x9D => {
+                    operations.push(Operator::ABSf);
+                }
+                0x9E => {
+                    operations.push(Operator::FLOORf);
+                }
+                0x9F => {
+                    operations.push(Operator::CEILf);
+                }
+                0xA0 => {
+                    operations.push(Operator::MINf);
+                }
+                0xA1 => {
+                    operations.push(Operator::MAXf);
+                }
+                0xA2 => {
+                    operations.push(Operator::POWf);
+                }
+                0xA3 => {
+                    let (u32_value, bytes_read) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::RORC(u32_value));
+                }
+                0xA4 => {
+                    let (u32_value, bytes_read) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::ROLC(u32_value));
+                }
+                0xA5 => {
+                    let (u32_value, bytes_read) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::LSRC(u32_value));
+                }
+                0xA6 => {
+                    let (u32_value, bytes_read) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::ASRC(u32_value));
+                }
+                0xA7 => {
+                    let (u32_value, bytes_read) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::LSLC(u32_value));
+                }
+                0xA8 => {
+                    let (u32_value, bytes_read) = read_next_u32(&program_binary, i, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::ASLC(u32_value));
+                }
+                0xA9 => {
+                    operations.push(Operator::RORD);
+                }
+                0xAA => {
+                    operations.push(Operator::ROLD);
+                }
+                0xAB => {
+                    operations.push(Operator::LSRD);
+                }
+                0xAC => {
+                    operations.push(Operator::ASRD);
+                }
+                0xAD => {
+                    operations.push(Operator::LSLD);
+                }
+                0xAE => {
+                    operations.push(Operator::ASLD);
+                }
+                0xAF => {
+                    operations.push(Operator::CLZ);
+                }
+                0xB0 => {
+                    operations.push(Operator::CTZ);
+                }
+                0xB1 => {
+                    operations.push(Operator::OVER);
+                }
+                0xB2 => {
+                    operations.push(Operator::ROT);
+                }
+                0xB3 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::PICK(usize_value));
+                }
+                0xB4 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::ROLL(usize_value));
+                }
+                0xB5 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::DROP(usize_value));
+                }
+                0xB6 => {
+                    operations.push(Operator::DEPTH);
+                }
+                0xB7 => {
+                    operations.push(Operator::CLEAR);
+                }
+                0xB8 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::ENTER(usize_value));
+                }
+                0xB9 => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::LOCAL_GET(usize_value));
+                }
+                0xBA => {
+                    let (usize_value, bytes_read) = read_next_usize(&program_binary, i, usize_width, Some(byte))?;
+                    i += bytes_read;
+                    operations.push(Operator::LOCAL_SET(usize_value));
+                }
                 _ => {
-                    panic!("Unknown opcode: {} at byte {}", byte, i);
+                    return Err(DecodeError {
+                        offset: i,
+                        expected: OperandKind::Opcode,
+                        opcode: Some(byte),
+                        kind: DecodeErrorKind::UnknownOpcode,
+                    });
                 }
             }
 
             i += 1;
         }
 
-        (operations, namespace.to_string())
+        Ok((operations, namespace.to_string()))
     }
 
-    fn read_next_usize(program_binary: &Vec<u8>, i: usize) -> (usize, usize) {
-        let read_next_n = mem::size_of::<usize>();
-        let mut next_n_bytes: Vec<u8> = Vec::new();
-        let mut _i = i;
-        for _ in 0..read_next_n {
-            _i = _i + 1;
-            next_n_bytes.push(program_binary[_i]);
+    // reads the n bytes after i, failing if the binary ends before all of them are available
+    fn read_next_bytes(
+        program_binary: &[u8],
+        i: usize,
+        read_next_n: usize,
+        expected: OperandKind,
+        opcode: Option<u8>,
+    ) -> Result<&[u8], DecodeError> {
+        let start = i + 1;
+        let end = start + read_next_n;
+        if end > program_binary.len() {
+            return Err(DecodeError {
+                offset: start,
+                expected,
+                opcode,
+                kind: DecodeErrorKind::UnexpectedEnd,
+            });
         }
-        let value = usize::from_be_bytes(next_n_bytes.as_slice().try_into().unwrap());
 
-        (value, read_next_n)
+        Ok(&program_binary[start..end])
     }
 
-    fn read_next_u32(program_binary: &Vec<u8>, i: usize) -> (u32, usize) {
-        let read_next_n = 4;
-        let mut next_n_bytes: Vec<u8> = Vec::new();
-        let mut _i = i;
-        for _ in 0..read_next_n {
-            _i = _i + 1;
-            next_n_bytes.push(program_binary[_i]);
+    // usize_width is 8 for versioned binaries, or the host usize width for the original layout
+    fn read_next_usize(
+        program_binary: &Vec<u8>,
+        i: usize,
+        usize_width: usize,
+        opcode: Option<u8>,
+    ) -> Result<(usize, usize), DecodeError> {
+        let read_next_n = usize_width;
+        let next_n_bytes = read_next_bytes(program_binary, i, read_next_n, OperandKind::Usize, opcode)?;
+        let mut value: u64 = 0;
+        for byte in next_n_bytes {
+            value = (value << 8) | (*byte as u64);
         }
-        let value = u32::from_be_bytes(next_n_bytes.as_slice().try_into().unwrap());
 
-        (value, read_next_n)
+        match usize::try_from(value) {
+            Ok(value) => Ok((value, read_next_n)),
+            // written by a wider machine than this one
+            Err(_) => Err(DecodeError {
+                offset: i + 1,
+                expected: OperandKind::Usize,
+                opcode,
+                kind: DecodeErrorKind::InvalidValue,
+            }),
+        }
     }
 
-    fn read_next_f32(program_binary: &Vec<u8>, i: usize) -> (f32, usize) {
+    fn read_next_u32(
+        program_binary: &Vec<u8>,
+        i: usize,
+        opcode: Option<u8>,
+    ) -> Result<(u32, usize), DecodeError> {
         let read_next_n = 4;
-        let mut next_n_bytes: Vec<u8> = Vec::new();
-        let mut _i = i;
-        for _ in 0..read_next_n {
-            _i = _i + 1;
-            next_n_bytes.push(program_binary[_i]);
-        }
-        let value = f32::from_be_bytes(next_n_bytes.as_slice().try_into().unwrap());
+        let next_n_bytes = read_next_bytes(program_binary, i, read_next_n, OperandKind::U32, opcode)?;
+        let value = u32::from_be_bytes(next_n_bytes.try_into().unwrap());
 
-        (value, read_next_n)
+        Ok((value, read_next_n))
     }
 
-    fn read_next_i32(program_binary: &Vec<u8>, i: usize) -> (i32, usize) {
+    fn read_next_f32(
+        program_binary: &Vec<u8>,
+        i: usize,
+        opcode: Option<u8>,
+    ) -> Result<(f32, usize), DecodeError> {
         let read_next_n = 4;
-        let mut next_n_bytes: Vec<u8> = Vec::new();
-        let mut _i = i;
-        for _ in 0..read_next_n {
-            _i = _i + 1;
-            next_n_bytes.push(program_binary[_i]);
-        }
-        let value = i32::from_be_bytes(next_n_bytes.as_slice().try_into().unwrap());
+        let next_n_bytes = read_next_bytes(program_binary, i, read_next_n, OperandKind::F32, opcode)?;
+        let value = f32::from_be_bytes(next_n_bytes.try_into().unwrap());
+
+        Ok((value, read_next_n))
+    }
+
+    fn read_next_i32(
+        program_binary: &Vec<u8>,
+        i: usize,
+        opcode: Option<u8>,
+    ) -> Result<(i32, usize), DecodeError> {
+        let read_next_n = 4;
+        let next_n_bytes = read_next_bytes(program_binary, i, read_next_n, OperandKind::I32, opcode)?;
+        let value = i32::from_be_bytes(next_n_bytes.try_into().unwrap());
+
+        Ok((value, read_next_n))
+    }
 
-        (value, read_next_n)
+    fn read_next_u8(
+        program_binary: &Vec<u8>,
+        i: usize,
+        opcode: Option<u8>,
+    ) -> Result<(u8, usize), DecodeError> {
+        let next_byte = read_next_bytes(program_binary, i, 1, OperandKind::U8, opcode)?;
+
+        Ok((next_byte[0], 1))
     }
 
-    fn read_next_bool(program_binary: &Vec<u8>, i: usize) -> (bool, usize) {
-        let next_byte = program_binary[i + 1];
+    fn read_next_bool(
+        program_binary: &Vec<u8>,
+        i: usize,
+        opcode: Option<u8>,
+    ) -> Result<(bool, usize), DecodeError> {
+        let next_byte = read_next_bytes(program_binary, i, 1, OperandKind::Bool, opcode)?[0];
         let value: bool = match next_byte {
             0x00 => false,
             0xff => true,
-            _ => panic!("Invalid bool value: {}", next_byte),
+            _ => {
+                return Err(DecodeError {
+                    offset: i + 1,
+                    expected: OperandKind::Bool,
+                    opcode,
+                    kind: DecodeErrorKind::InvalidValue,
+                })
+            }
         };
 
-        return (value, 1);
+        return Ok((value, 1));
     }
 
-    fn read_next_string(program_binary: &Vec<u8>, i: usize, string_size: usize) -> (String, usize) {
-        let mut next_n_bytes: Vec<u8> = Vec::new();
-        let mut _i = i;
-        for _ in 0..string_size {
-            _i = _i + 1;
-            next_n_bytes.push(program_binary[_i]);
-        }
-        //convert each byte to a char
-        let mut string_chars: Vec<char> = Vec::new();
-        for byte in next_n_bytes.iter() {
-            string_chars.push(*byte as char);
+    fn read_next_string(
+        program_binary: &Vec<u8>,
+        i: usize,
+        string_size: usize,
+        opcode: Option<u8>,
+    ) -> Result<(String, usize), DecodeError> {
+        // a corrupt length prefix can be anything, so check it against the remaining bytes before reading
+        if string_size > program_binary.len() {
+            return Err(DecodeError {
+                offset: i + 1,
+                expected: OperandKind::String,
+                opcode,
+                kind: DecodeErrorKind::UnexpectedEnd,
+            });
         }
-        let value = String::from_utf8_lossy(&*next_n_bytes).to_string();
+        let next_n_bytes = read_next_bytes(program_binary, i, string_size, OperandKind::String, opcode)?;
+        let value = String::from_utf8_lossy(next_n_bytes).to_string();
 
-        let len = value.len();
-        (value, len)
+        // the number of bytes in the binary, not in the decoded string: lossy decoding can change the length
+        Ok((value, string_size))
     }
 }
@@ -0,0 +1,337 @@
+pub mod debugger {
+    use crate::asm_parser::asm_parser::{str_to_u32, str_to_usize};
+    use crate::stal_dll::stal_dll::StalDynamicLibrary;
+    use crate::stalfos::ops::Operator;
+    use crate::stalfos::VM;
+    use crate::vm_error::vm_error::VmError;
+    use std::collections::{BTreeSet, HashMap};
+    use std::io::{BufRead, Write};
+
+    // why the debugger handed control back
+    #[derive(Debug, Clone)]
+    pub enum StopReason {
+        // a step or step over completed
+        Stepped,
+        // the program counter reached a breakpoint
+        Breakpoint(usize),
+        // the program set signal_finished
+        Finished,
+        // the VM faulted. the program counter is left on the failing operation
+        Error(VmError),
+    }
+
+    ///
+    /// Runs a program one operation at a time. Breakpoints are program counters; labels are
+    /// resolved through the jmp_table when the breakpoint is added.
+    ///
+    pub struct Debugger {
+        pub vm: VM,
+        libs: HashMap<String, StalDynamicLibrary>,
+        breakpoints: BTreeSet<usize>,
+        // set once the program finishes or faults, nothing else can be executed after that
+        halted: Option<StopReason>,
+    }
+
+    const HELP: &str = "commands:
+  break <label|pc>      (b)  add a breakpoint
+  delete <label|pc>     (d)  remove a breakpoint
+  breakpoints                list breakpoints
+  step [n]              (s)  execute n operations (default 1)
+  next                  (n)  step, running through CALL/JMP/LIBCALL until the call returns
+  continue              (c)  run until a breakpoint, the end of the program or an error
+  where                 (w)  show the program counter and operation
+  print <what> [start] [len]   (p) what is one of stack, memory, static_alloc_table,
+                             jmp_table, stack_frame_pointers, output
+  poke stack <index> <value>   overwrite a stack value (index 0 is the bottom)
+  poke memory <address> <value>
+  push <value>               push a value onto the stack
+  help                  (h)
+  quit                  (q)";
+
+    impl Debugger {
+        pub fn new(program: Vec<Operator>) -> Result<Debugger, VmError> {
+            let mut vm = VM::new();
+            vm.add_ops(program).prepare()?;
+            Ok(Debugger {
+                vm,
+                libs: HashMap::new(),
+                breakpoints: BTreeSet::new(),
+                halted: None,
+            })
+        }
+
+        // resolves a label or pc (decimal, 0x or 0b) to a program counter
+        fn resolve(&self, target: &str) -> Result<usize, String> {
+            if let Some(&pc) = self.vm.jmp_table.get(target.trim_start_matches('.')) {
+                if pc >= self.vm.program.len() {
+                    return Err(format!(
+                        "label '{}' points at pc {}, outside of the program",
+                        target, pc
+                    ));
+                }
+                return Ok(pc);
+            }
+            match str_to_usize(target) {
+                Some(pc) if pc < self.vm.program.len() => Ok(pc),
+                Some(pc) => Err(format!("pc {} is outside of the program", pc)),
+                None => Err(format!("unknown label '{}'", target)),
+            }
+        }
+
+        pub fn add_breakpoint(&mut self, target: &str) -> Result<usize, String> {
+            let pc = self.resolve(target)?;
+            self.breakpoints.insert(pc);
+            Ok(pc)
+        }
+
+        pub fn remove_breakpoint(&mut self, target: &str) -> Result<usize, String> {
+            let pc = self.resolve(target)?;
+            if !self.breakpoints.remove(&pc) {
+                return Err(format!("no breakpoint at {}", pc));
+            }
+            Ok(pc)
+        }
+
+        pub fn breakpoints(&self) -> &BTreeSet<usize> {
+            &self.breakpoints
+        }
+
+        pub fn halted(&self) -> Option<&StopReason> {
+            self.halted.as_ref()
+        }
+
+        // executes one operation, recording when the program can no longer continue
+        fn execute_one(&mut self) -> Option<StopReason> {
+            if let Some(reason) = &self.halted {
+                return Some(reason.clone());
+            }
+
+            let stopped = match self.vm.step(&mut self.libs) {
+                Err(error) => Some(StopReason::Error(error)),
+                Ok(vm) if vm.signal_finished => Some(StopReason::Finished),
+                Ok(_) => None,
+            };
+            self.halted = stopped.clone();
+            stopped
+        }
+
+        pub fn step(&mut self) -> StopReason {
+            self.execute_one().unwrap_or(StopReason::Stepped)
+        }
+
+        ///
+        /// Steps one operation. If that operation opened a stack frame (a CALL or JMP), runs until
+        /// the frame is closed again, by RET or an exception caught further out, or a breakpoint is
+        /// hit. LIBCALLs run the library function to completion in a single step already.
+        ///
+        pub fn step_over(&mut self) -> StopReason {
+            let depth = self.vm.stack_frame_pointers.len();
+            if let Some(reason) = self.execute_one() {
+                return reason;
+            }
+
+            while self.vm.stack_frame_pointers.len() > depth {
+                if self.breakpoints.contains(&self.vm.program_counter) {
+                    return StopReason::Breakpoint(self.vm.program_counter);
+                }
+                if let Some(reason) = self.execute_one() {
+                    return reason;
+                }
+            }
+            StopReason::Stepped
+        }
+
+        // runs until a breakpoint. always executes at least one operation, so continuing from a breakpoint moves past it
+        pub fn resume(&mut self) -> StopReason {
+            loop {
+                if let Some(reason) = self.execute_one() {
+                    return reason;
+                }
+                if self.breakpoints.contains(&self.vm.program_counter) {
+                    return StopReason::Breakpoint(self.vm.program_counter);
+                }
+            }
+        }
+
+        fn describe_stop(&self, reason: &StopReason, out: &mut dyn Write) -> std::io::Result<()> {
+            match reason {
+                StopReason::Stepped => self.print_location(out),
+                StopReason::Breakpoint(pc) => {
+                    writeln!(out, "breakpoint at {}", pc)?;
+                    self.print_location(out)
+                }
+                StopReason::Finished => writeln!(out, "program finished"),
+                StopReason::Error(error) => writeln!(out, "program faulted: {}", error),
+            }
+        }
+
+        fn print_location(&self, out: &mut dyn Write) -> std::io::Result<()> {
+            let pc = self.vm.program_counter;
+            match self.vm.program.get(pc) {
+                Some(op) => writeln!(out, "{}: {:?}", pc, op),
+                None => writeln!(out, "{}: <outside of the program>", pc),
+            }
+        }
+
+        fn print(&self, args: &[&str], out: &mut dyn Write) -> std::io::Result<()> {
+            let vm = &self.vm;
+            match args.first().copied() {
+                Some("stack") => {
+                    for (i, v) in vm.stack.iter().enumerate() {
+                        writeln!(out, "{:>6}: {:#010x} ({})", i, v, v)?;
+                    }
+                    writeln!(out, "{} values", vm.stack.len())
+                }
+                Some("memory") => {
+                    let start = args.get(1).and_then(|s| str_to_usize(s)).unwrap_or(0);
+                    let len = args.get(2).and_then(|s| str_to_usize(s)).unwrap_or(64);
+                    let end = start.saturating_add(len).min(vm.memory.len());
+                    for address in start..end {
+                        let v = vm.memory[address];
+                        writeln!(out, "{:>6}: {:#010x} ({})", address, v, v)?;
+                    }
+                    writeln!(out, "{} words of memory", vm.memory.len())
+                }
+                Some("static_alloc_table") => {
+                    for (ptr, (location, size)) in vm.static_alloc_table.iter() {
+                        writeln!(out, "{:>6}: location {}, size {}", ptr, location, size)?;
+                    }
+                    Ok(())
+                }
+                Some("jmp_table") => {
+                    let mut labels: Vec<(&String, &usize)> = vm.jmp_table.iter().collect();
+                    labels.sort_by_key(|(_, pc)| **pc);
+                    for (label, pc) in labels {
+                        writeln!(out, "{:>6}: {}", pc, label)?;
+                    }
+                    Ok(())
+                }
+                Some("stack_frame_pointers") => {
+                    for (i, frame) in vm.stack_frame_pointers.iter().enumerate() {
+                        writeln!(
+                            out,
+                            "{:>6}: from {} to {}, allocations {:?}, locals {:?}",
+                            i, frame.from, frame.to, frame.allocations, frame.locals
+                        )?;
+                    }
+                    Ok(())
+                }
+                Some("output") => writeln!(out, "{:?}", vm.output),
+                _ => writeln!(
+                    out,
+                    "print one of stack, memory, static_alloc_table, jmp_table, stack_frame_pointers, output"
+                ),
+            }
+        }
+
+        fn poke(&mut self, args: &[&str], out: &mut dyn Write) -> std::io::Result<()> {
+            let (target, index, value) = match args {
+                [target, index, value] => (*target, str_to_usize(index), str_to_u32(value)),
+                _ => return writeln!(out, "usage: poke <stack|memory> <index> <value>"),
+            };
+            let (index, value) = match (index, value) {
+                (Some(index), Some(value)) => (index, value),
+                _ => return writeln!(out, "invalid index or value"),
+            };
+
+            let slot = match target {
+                "stack" => self.vm.stack.get_mut(index),
+                "memory" => self.vm.memory.get_mut(index),
+                _ => return writeln!(out, "can only poke stack or memory"),
+            };
+            match slot {
+                Some(slot) => {
+                    *slot = value;
+                    writeln!(out, "{}[{}] = {}", target, index, value)
+                }
+                None => writeln!(out, "{} has no index {}", target, index),
+            }
+        }
+
+        ///
+        /// Runs a single debugger command, writing its result to out. Returns false when the
+        /// command was quit.
+        ///
+        pub fn run_command(&mut self, line: &str, out: &mut dyn Write) -> std::io::Result<bool> {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let (command, args) = match words.split_first() {
+                Some((command, args)) => (*command, args),
+                None => return Ok(true),
+            };
+
+            match command {
+                "break" | "b" => match args.first().map(|target| self.add_breakpoint(target)) {
+                    Some(Ok(pc)) => writeln!(out, "breakpoint at {}", pc)?,
+                    Some(Err(error)) => writeln!(out, "{}", error)?,
+                    None => writeln!(out, "usage: break <label|pc>")?,
+                },
+                "delete" | "d" => match args.first().map(|target| self.remove_breakpoint(target)) {
+                    Some(Ok(pc)) => writeln!(out, "removed breakpoint at {}", pc)?,
+                    Some(Err(error)) => writeln!(out, "{}", error)?,
+                    None => writeln!(out, "usage: delete <label|pc>")?,
+                },
+                "breakpoints" => {
+                    for pc in self.breakpoints.iter() {
+                        match self.vm.program.get(*pc) {
+                            Some(op) => writeln!(out, "{}: {:?}", pc, op)?,
+                            None => writeln!(out, "{}: <out of range>", pc)?,
+                        }
+                    }
+                }
+                "step" | "s" => {
+                    let count = args.first().and_then(|s| str_to_usize(s)).unwrap_or(1);
+                    let mut reason = StopReason::Stepped;
+                    for i in 0..count {
+                        reason = self.step();
+                        if !matches!(reason, StopReason::Stepped) {
+                            break;
+                        }
+                        let pc = self.vm.program_counter;
+                        if i + 1 < count && self.breakpoints.contains(&pc) {
+                            reason = StopReason::Breakpoint(pc);
+                            break;
+                        }
+                    }
+                    self.describe_stop(&reason, out)?;
+                }
+                "next" | "n" => {
+                    let reason = self.step_over();
+                    self.describe_stop(&reason, out)?;
+                }
+                "continue" | "c" => {
+                    let reason = self.resume();
+                    self.describe_stop(&reason, out)?;
+                }
+                "where" | "w" => self.print_location(out)?,
+                "print" | "p" => self.print(args, out)?,
+                "poke" => self.poke(args, out)?,
+                "push" => match args.first().and_then(|s| str_to_u32(s)) {
+                    Some(value) => self.vm.stack.push(value),
+                    None => writeln!(out, "usage: push <value>")?,
+                },
+                "help" | "h" => writeln!(out, "{}", HELP)?,
+                "quit" | "q" => return Ok(false),
+                _ => writeln!(out, "unknown command '{}', try help", command)?,
+            }
+
+            Ok(true)
+        }
+
+        // reads commands from input until quit or the end of input
+        pub fn repl(&mut self, input: &mut dyn BufRead, out: &mut dyn Write) -> std::io::Result<()> {
+            self.print_location(out)?;
+            loop {
+                write!(out, "(stalfos) ")?;
+                out.flush()?;
+
+                let mut line = String::new();
+                if input.read_line(&mut line)? == 0 {
+                    return Ok(());
+                }
+                if !self.run_command(&line, out)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,272 @@
+pub mod disassembler {
+    use crate::stalfos::ops::Operator;
+
+    ///
+    /// Renders a program back to .sta source that asm_parser accepts. Every operator is written
+    /// on its own line, so program counters (and therefore JMP_DEF targets) are unchanged and
+    /// assembling the output gives the same binary the program was decoded from.
+    ///
+    pub fn disassemble(program: &[Operator], namespace: &str) -> String {
+        let mut source = String::new();
+
+        if !namespace.is_empty() {
+            source.push_str(&format!("#<{}>\n", namespace));
+        }
+
+        let mut in_function = false;
+        for op in program {
+            match op {
+                Operator::LABEL(label) => {
+                    in_function = true;
+                    source.push_str(&label_line(label));
+                }
+                _ => {
+                    // indent the body of each function under its label
+                    if in_function {
+                        source.push_str("    ");
+                    }
+                    source.push_str(&operation_line(op));
+                }
+            }
+            source.push('\n');
+        }
+
+        source
+    }
+
+    // .label when the name survives the shorthand unchanged, LABEL "name" otherwise
+    fn label_line(label: &String) -> String {
+        let is_plain = !label.is_empty()
+            && !label.contains(|c: char| c == '"' || c == '.' || c == ';' || c.is_whitespace());
+
+        if is_plain {
+            format!(".{}", label)
+        } else {
+            format!("LABEL {}", quote(label))
+        }
+    }
+
+    fn quote(s: &str) -> String {
+        let mut quoted = String::from("\"");
+        for c in s.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                '\t' => quoted.push_str("\\t"),
+                '\0' => quoted.push_str("\\0"),
+                _ => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    // Debug prints the shortest representation that reads back to the same f32, but writes infinity as inf
+    // which the parser would read as a trailing f suffix
+    fn float(v: &f32) -> String {
+        if v.is_infinite() {
+            if v.is_sign_negative() {
+                return "-infinity".to_string();
+            }
+            return "infinity".to_string();
+        }
+        format!("{:?}", v)
+    }
+
+    fn operation_line(op: &Operator) -> String {
+        match op {
+            Operator::PUSH(v) => format!("PUSH {}", v),
+            Operator::LOAD(v) => format!("LOAD {}", v),
+            Operator::LOADD(v) => format!("LOADD {}", v),
+            Operator::CONST_U(ptr, v) => format!("CONST_U {} {}", ptr, v),
+            Operator::CONST_F(ptr, v) => format!("CONST_F {} {}", ptr, float(v)),
+            Operator::CONST_I(ptr, v) => format!("CONST_I {} {}", ptr, v),
+            Operator::CONST_B(ptr, v) => format!("CONST_B {} {}", ptr, v),
+            Operator::CONST_S(ptr, s) => format!("CONST_S {} {}", ptr, quote(s)),
+            Operator::LOAD_CONST(v) => format!("LOAD_CONST {}", v),
+            Operator::POP => "POP".to_string(),
+            Operator::ALLOC(ptr, size) => format!("ALLOC {} {}", ptr, size),
+            Operator::DEALLOC(v) => format!("DEALLOC {}", v),
+            Operator::POPS(v) => format!("POPS {}", v),
+            Operator::GETLEN(v) => format!("GETLEN {}", v),
+            Operator::GETBYTELEN(v) => format!("GETBYTELEN {}", v),
+            Operator::GETBYTE(ptr, index) => format!("GETBYTE {} {}", ptr, index),
+            Operator::GETWORD(ptr, index) => format!("GETWORD {} {}", ptr, index),
+            Operator::SETBYTE(ptr, index, v) => format!("SETBYTE {} {} {}", ptr, index, v),
+            Operator::SETWORD(ptr, index, v) => format!("SETWORD {} {} {}", ptr, index, v),
+            Operator::DUP => "DUP".to_string(),
+            Operator::DUPO(v) => format!("DUPO {}", v),
+            Operator::SWAP => "SWAP".to_string(),
+            Operator::ADDu => "ADDu".to_string(),
+            Operator::ADDi => "ADDi".to_string(),
+            Operator::ADDfi => "ADDfi".to_string(),
+            Operator::ADDif => "ADDif".to_string(),
+            Operator::ADDf => "ADDf".to_string(),
+            Operator::SUBu => "SUBu".to_string(),
+            Operator::SUBi => "SUBi".to_string(),
+            Operator::SUBfi => "SUBfi".to_string(),
+            Operator::SUBif => "SUBif".to_string(),
+            Operator::SUBf => "SUBf".to_string(),
+            Operator::MULu => "MULu".to_string(),
+            Operator::MULi => "MULi".to_string(),
+            Operator::MULfi => "MULfi".to_string(),
+            Operator::MULif => "MULif".to_string(),
+            Operator::MULf => "MULf".to_string(),
+            Operator::DIVu => "DIVu".to_string(),
+            Operator::DIVi => "DIVi".to_string(),
+            Operator::DIVfi => "DIVfi".to_string(),
+            Operator::DIVif => "DIVif".to_string(),
+            Operator::DIVf => "DIVf".to_string(),
+            Operator::MODu => "MODu".to_string(),
+            Operator::MODi => "MODi".to_string(),
+            Operator::MODfi => "MODfi".to_string(),
+            Operator::MODif => "MODif".to_string(),
+            Operator::MODf => "MODf".to_string(),
+            Operator::ROR => "ROR".to_string(),
+            Operator::ROL => "ROL".to_string(),
+            Operator::LSR => "LSR".to_string(),
+            Operator::ASR => "ASR".to_string(),
+            Operator::LSL => "LSL".to_string(),
+            Operator::ASL => "ASL".to_string(),
+            Operator::NEG => "NEG".to_string(),
+            Operator::AND => "AND".to_string(),
+            Operator::XOR => "XOR".to_string(),
+            Operator::OR => "OR".to_string(),
+            Operator::NOR => "NOR".to_string(),
+            Operator::NAND => "NAND".to_string(),
+            Operator::CNT => "CNT".to_string(),
+            Operator::CMP => "CMP".to_string(),
+            Operator::JMP_SCAN => "JMP_SCAN".to_string(),
+            Operator::JMP(label) => format!("JMP {}", quote(label)),
+            Operator::JMPo(label) => format!("JMPo {}", quote(label)),
+            Operator::JMPe(label) => format!("JMPe {}", quote(label)),
+            Operator::JMPne(label) => format!("JMPne {}", quote(label)),
+            Operator::JMPs(left, right) => format!("JMPs {} {}", quote(left), quote(right)),
+            Operator::JMP_DEF(label, pc) => format!("JMP_DEF {} {}", quote(label), pc),
+            Operator::LABEL(label) => format!("LABEL {}", quote(label)),
+            Operator::SYSCALL(id, n_args) => format!("SYSCALL {} {}", id, n_args),
+            Operator::SYSCALLD(id) => format!("SYSCALLD {}", id),
+            Operator::EXCEPT_THROW => "EXCEPT_THROW".to_string(),
+            Operator::EXCEPT_CATCH(label) => format!("EXCEPT_CATCH {}", quote(label)),
+            Operator::RET => "RET".to_string(),
+            Operator::EMIT => "EMIT".to_string(),
+            Operator::EMITS(v) => format!("EMITS {}", v),
+            Operator::EMITW(v) => format!("EMITW {}", v),
+            Operator::EMITD(v) => format!("EMITD {}", v),
+            Operator::DJMP => "DJMP".to_string(),
+            Operator::DJMPe => "DJMPe".to_string(),
+            Operator::DJMPne => "DJMPne".to_string(),
+            Operator::DALLOC(v) => format!("DALLOC {}", v),
+            Operator::LIBLOAD(library) => format!("LIBLOAD {}", quote(library)),
+            Operator::DLIBLOAD => "DLIBLOAD".to_string(),
+            Operator::LIBCALL(library, label) => {
+                format!("LIBCALL {} {}", quote(library), quote(label))
+            }
+            Operator::DLIBCALL(label) => format!("DLIBCALL {}", quote(label)),
+            Operator::LIBDCALL(library) => format!("LIBDCALL {}", quote(library)),
+            Operator::DLIBDCALL => "DLIBDCALL".to_string(),
+            Operator::DNEW => "DNEW".to_string(),
+            Operator::DGETSIZE => "DGETSIZE".to_string(),
+            Operator::DLOADVALUE => "DLOADVALUE".to_string(),
+            Operator::DDEALLOC => "DDEALLOC".to_string(),
+            Operator::DGETWORD => "DGETWORD".to_string(),
+            Operator::DSETWORD => "DSETWORD".to_string(),
+            Operator::DGETBYTE => "DGETBYTE".to_string(),
+            Operator::DSETBYTE => "DSETBYTE".to_string(),
+            Operator::ALLOC_PERSIST(ptr, size) => format!("ALLOC_PERSIST {} {}", ptr, size),
+            Operator::CALL(label) => format!("CALL {}", quote(label)),
+            Operator::CALLD => "CALLD".to_string(),
+            Operator::BR(label) => format!("BR {}", quote(label)),
+            Operator::BRo(label) => format!("BRo {}", quote(label)),
+            Operator::BRe(label) => format!("BRe {}", quote(label)),
+            Operator::BRne(label) => format!("BRne {}", quote(label)),
+            Operator::BRs(left, right) => format!("BRs {} {}", quote(left), quote(right)),
+            Operator::DBR => "DBR".to_string(),
+            Operator::DBRe => "DBRe".to_string(),
+            Operator::DBRne => "DBRne".to_string(),
+            Operator::EXCEPT_THROWC(code) => format!("EXCEPT_THROWC {}", code),
+            Operator::EXCEPT_THROWD => "EXCEPT_THROWD".to_string(),
+            Operator::EXCEPT_THROWP(code, ptr) => format!("EXCEPT_THROWP {} {}", code, ptr),
+            Operator::EXCEPT_CATCH_RANGE(label, low, high) => {
+                format!("EXCEPT_CATCH_RANGE {} {} {}", quote(label), low, high)
+            }
+            Operator::EXCEPT_FINALLY(label) => format!("EXCEPT_FINALLY {}", quote(label)),
+            Operator::EXCEPT_END => "EXCEPT_END".to_string(),
+            Operator::EXCEPT_CODE => "EXCEPT_CODE".to_string(),
+            Operator::EXCEPT_PAYLOAD(ptr) => format!("EXCEPT_PAYLOAD {}", ptr),
+            Operator::CMPu => "CMPu".to_string(),
+            Operator::CMPi => "CMPi".to_string(),
+            Operator::CMPf => "CMPf".to_string(),
+            Operator::JMPlt(label) => format!("JMPlt {}", quote(label)),
+            Operator::JMPgt(label) => format!("JMPgt {}", quote(label)),
+            Operator::JMPle(label) => format!("JMPle {}", quote(label)),
+            Operator::JMPge(label) => format!("JMPge {}", quote(label)),
+            Operator::BRlt(label) => format!("BRlt {}", quote(label)),
+            Operator::BRgt(label) => format!("BRgt {}", quote(label)),
+            Operator::BRle(label) => format!("BRle {}", quote(label)),
+            Operator::BRge(label) => format!("BRge {}", quote(label)),
+            Operator::SETREG64(register) => format!("SETREG64 {}", register),
+            Operator::GETREG64(register) => format!("GETREG64 {}", register),
+            Operator::SETREG128(register) => format!("SETREG128 {}", register),
+            Operator::GETREG128(register) => format!("GETREG128 {}", register),
+            Operator::ADD64u => "ADD64u".to_string(),
+            Operator::ADD64i => "ADD64i".to_string(),
+            Operator::SUB64u => "SUB64u".to_string(),
+            Operator::SUB64i => "SUB64i".to_string(),
+            Operator::MUL64u => "MUL64u".to_string(),
+            Operator::MUL64i => "MUL64i".to_string(),
+            Operator::DIV64u => "DIV64u".to_string(),
+            Operator::DIV64i => "DIV64i".to_string(),
+            Operator::LSL64 => "LSL64".to_string(),
+            Operator::LSR64 => "LSR64".to_string(),
+            Operator::ASR64 => "ASR64".to_string(),
+            Operator::CMP64u => "CMP64u".to_string(),
+            Operator::CMP64i => "CMP64i".to_string(),
+            Operator::ADD128u => "ADD128u".to_string(),
+            Operator::SUB128u => "SUB128u".to_string(),
+            Operator::MUL128u => "MUL128u".to_string(),
+            Operator::DIV128u => "DIV128u".to_string(),
+            Operator::LSL128 => "LSL128".to_string(),
+            Operator::LSR128 => "LSR128".to_string(),
+            Operator::CMP128u => "CMP128u".to_string(),
+            Operator::LOADf(ptr) => format!("LOADf {}", ptr),
+            Operator::LOADi(ptr) => format!("LOADi {}", ptr),
+            Operator::ITOF => "ITOF".to_string(),
+            Operator::FTOI => "FTOI".to_string(),
+            Operator::UTOF => "UTOF".to_string(),
+            Operator::SQRTf => "SQRTf".to_string(),
+            Operator::ABSf => "ABSf".to_string(),
+            Operator::FLOORf => "FLOORf".to_string(),
+            Operator::CEILf => "CEILf".to_string(),
+            Operator::MINf => "MINf".to_string(),
+            Operator::MAXf => "MAXf".to_string(),
+            Operator::POWf => "POWf".to_string(),
+            Operator::RORC(amount) => format!("RORC {}", amount),
+            Operator::ROLC(amount) => format!("ROLC {}", amount),
+            Operator::LSRC(amount) => format!("LSRC {}", amount),
+            Operator::ASRC(amount) => format!("ASRC {}", amount),
+            Operator::LSLC(amount) => format!("LSLC {}", amount),
+            Operator::ASLC(amount) => format!("ASLC {}", amount),
+            Operator::RORD => "RORD".to_string(),
+            Operator::ROLD => "ROLD".to_string(),
+            Operator::LSRD => "LSRD".to_string(),
+            Operator::ASRD => "ASRD".to_string(),
+            Operator::LSLD => "LSLD".to_string(),
+            Operator::ASLD => "ASLD".to_string(),
+            Operator::CLZ => "CLZ".to_string(),
+            Operator::CTZ => "CTZ".to_string(),
+            Operator::OVER => "OVER".to_string(),
+            Operator::ROT => "ROT".to_string(),
+            Operator::PICK(n) => format!("PICK {}", n),
+            Operator::ROLL(n) => format!("ROLL {}", n),
+            Operator::DROP(n) => format!("DROP {}", n),
+            Operator::DEPTH => "DEPTH".to_string(),
+            Operator::CLEAR => "CLEAR".to_string(),
+            Operator::ENTER(n) => format!("ENTER {}", n),
+            Operator::LOCAL_GET(index) => format!("LOCAL_GET {}", index),
+            Operator::LOCAL_SET(index) => format!("LOCAL_SET {}", index),
+        }
+    }
+}
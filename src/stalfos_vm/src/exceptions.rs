@@ -0,0 +1,105 @@
+pub mod exceptions {
+    use crate::ops::ops::Operator;
+    use crate::vm_error::vm_error::VmError;
+    use std::collections::{BTreeMap, HashMap};
+
+    // codes of the exceptions thrown for runtime faults when vm.trap_faults is set. user codes should stay below FAULT_CODES
+    pub const FAULT_CODES: u32 = 0xFFFF_0000;
+    pub const DIVIDE_BY_ZERO: u32 = FAULT_CODES + 1;
+    pub const OUT_OF_BOUNDS: u32 = FAULT_CODES + 2;
+    pub const STACK_UNDERFLOW: u32 = FAULT_CODES + 3;
+    pub const UNKNOWN_ALLOCATION: u32 = FAULT_CODES + 4;
+    pub const INVALID_POINTER: u32 = FAULT_CODES + 5;
+    pub const UNKNOWN_LABEL: u32 = FAULT_CODES + 6;
+    pub const UNKNOWN_SYSCALL: u32 = FAULT_CODES + 7;
+    pub const LIBRARY_FAULT: u32 = FAULT_CODES + 8;
+    pub const MEMORY_LIMIT: u32 = FAULT_CODES + 9;
+
+    ///
+    /// The exception being thrown, or handled once a catch has been found. The payload is an
+    /// allocation taken away from its identifier when it was thrown, so unwinding does not free it.
+    /// EXCEPT_PAYLOAD gives it an identifier again.
+    ///
+    #[derive(Debug, Clone)]
+    pub struct Exception {
+        pub code: u32,
+        // (location, size) of the payload allocation
+        pub payload: Option<(usize, u32)>,
+        // where it was thrown
+        pub pc: usize,
+        // set while an EXCEPT_FINALLY block runs to the pc of the EXCEPT_FINALLY, EXCEPT_END carries on unwinding from there
+        pub finally_at: Option<usize>,
+    }
+
+    impl Exception {
+        pub fn new(code: u32, payload: Option<(usize, u32)>, pc: usize) -> Exception {
+            Exception {
+                code,
+                payload,
+                pc,
+                finally_at: None,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum HandlerKind {
+        // EXCEPT_CATCH (every code) or EXCEPT_CATCH_RANGE, codes low..=high
+        Catch { low: u32, high: u32 },
+        // EXCEPT_FINALLY, runs for every code and then carries on unwinding
+        Finally,
+    }
+
+    // a catch or finally in the program, target is the program counter of its label
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Handler {
+        pub kind: HandlerKind,
+        pub target: usize,
+    }
+
+    impl Handler {
+        pub fn handles(&self, code: u32) -> bool {
+            match self.kind {
+                HandlerKind::Catch { low, high } => low <= code && code <= high,
+                HandlerKind::Finally => true,
+            }
+        }
+    }
+
+    ///
+    /// Finds every EXCEPT_CATCH, EXCEPT_CATCH_RANGE and EXCEPT_FINALLY in the program, keyed by
+    /// their program counter. A throw uses the nearest handler before it in the function that is
+    /// running, then the nearest one before the call in each caller.
+    ///
+    pub fn handler_table(
+        program: &[Operator],
+        jmp_table: &HashMap<String, usize>,
+    ) -> Result<BTreeMap<usize, Handler>, VmError> {
+        let mut handlers = BTreeMap::new();
+
+        for (pc, op) in program.iter().enumerate() {
+            let (label, kind) = match op {
+                Operator::EXCEPT_CATCH(label) => (label, HandlerKind::Catch { low: 0, high: u32::MAX }),
+                Operator::EXCEPT_CATCH_RANGE(label, low, high) => {
+                    (label, HandlerKind::Catch { low: *low, high: *high })
+                }
+                Operator::EXCEPT_FINALLY(label) => (label, HandlerKind::Finally),
+                _ => continue,
+            };
+
+            let target = match jmp_table.get(label) {
+                Some(target) => *target,
+                None => {
+                    return Err(VmError::UnknownLabel {
+                        pc,
+                        op: Box::new(op.clone()),
+                        label: label.clone(),
+                    })
+                }
+            };
+            handlers.insert(pc, Handler { kind, target });
+        }
+
+        Ok(handlers)
+    }
+}
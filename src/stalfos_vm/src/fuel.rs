@@ -0,0 +1,56 @@
+pub mod fuel {
+    use crate::ops::ops::Operator;
+    use crate::vm_error::vm_error::VmError;
+    use std::collections::HashMap;
+    use std::mem::{discriminant, Discriminant};
+
+    // how a call to VM::run_for ended
+    #[derive(Debug, Clone)]
+    pub enum RunOutcome {
+        // the program set signal_finished
+        Finished,
+        // the next operation costs more fuel than is left. it has not been executed, run_for again to resume
+        OutOfFuel,
+        // the VM faulted, the program counter is left on the failing operation
+        Trapped(VmError),
+    }
+
+    ///
+    /// The fuel each operator costs in VM::run_for. Costs are per Operator variant, operands are
+    /// ignored, so CONST_S(1, "a") and CONST_S(2, "abc") cost the same. Anything without its own
+    /// cost uses default_cost.
+    ///
+    #[derive(Debug, Clone)]
+    pub struct FuelCosts {
+        pub default_cost: u64,
+        costs: HashMap<Discriminant<Operator>, u64>,
+    }
+
+    impl FuelCosts {
+        pub fn new(default_cost: u64) -> FuelCosts {
+            FuelCosts {
+                default_cost,
+                costs: HashMap::new(),
+            }
+        }
+
+        // sets the cost of every operator of the same variant as op
+        pub fn set_cost(&mut self, op: &Operator, cost: u64) -> &mut FuelCosts {
+            self.costs.insert(discriminant(op), cost);
+            return self;
+        }
+
+        pub fn cost_of(&self, op: &Operator) -> u64 {
+            match self.costs.get(&discriminant(op)) {
+                Some(cost) => *cost,
+                None => self.default_cost,
+            }
+        }
+    }
+
+    impl Default for FuelCosts {
+        fn default() -> FuelCosts {
+            FuelCosts::new(1)
+        }
+    }
+}
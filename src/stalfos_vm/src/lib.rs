@@ -1,8 +1,16 @@
 extern crate core;
 
 pub mod asm_parser;
+pub mod allocator;
 pub mod assembler;
+pub mod debugger;
+pub mod disassembler;
+pub mod exceptions;
+pub mod fuel;
+pub mod limits;
+mod lowering;
 pub mod ops;
+pub mod vm_error;
 
 mod op_calls;
 mod stal_dll;
@@ -12,8 +20,20 @@ pub mod stalfos {
     pub use crate::ops::ops;
     use crate::ops::ops::Operator;
     use crate::stal_dll::stal_dll::{StalDynamicInvocation, StalDynamicLibrary};
+    pub use crate::stal_dll::stal_dll::LibraryLoadError;
+    pub use crate::vm_error::vm_error::VmError;
+    pub use crate::fuel::fuel::{FuelCosts, RunOutcome};
+    pub use crate::limits::limits::Limits;
+    pub use crate::allocator::allocator::Allocator;
+    pub use crate::exceptions::exceptions::{Exception, Handler, HandlerKind};
+    use crate::exceptions::exceptions::handler_table;
+    use crate::lowering::lowering::{lower, Instruction};
     use std::borrow::{Borrow, BorrowMut};
+    use std::cmp::Ordering;
     use std::collections::{BTreeMap, HashMap};
+    use std::fmt;
+    use std::io::Write;
+    use std::rc::Rc;
 
     pub struct VM {
         pub stack: Vec<u32>,
@@ -23,12 +43,17 @@ pub mod stalfos {
 
         //<preset pointer, (location, size)>
         pub static_alloc_table: BTreeMap<usize, (usize, u32)>,
-        pub dynamic_allocations:Vec<(usize,u32)>,
+        // type of each identifier in the static_alloc_table that was stored by a CONST_
+        pub const_types: BTreeMap<usize, ConstType>,
+        //<location, size> of each allocation made through a heap pointer (DNEW)
+        pub dynamic_allocations: BTreeMap<usize, u32>,
+        // hands out (and takes back) the memory behind both kinds of allocation
+        pub allocator: Allocator,
         // label, address
         pub jmp_table: HashMap<String, usize>,
 
-        // from, to
-        pub stack_frame_pointers: Vec<(usize, usize)>,
+        // one frame per JMP (or catch) that has not returned yet, innermost last
+        pub stack_frame_pointers: Vec<StackFrame>,
 
         // output words, from EMIT functions
         pub output: Vec<u32>,
@@ -42,18 +67,106 @@ pub mod stalfos {
         // if the last arithmetic operation overflowed, this will be set
         pub signal_overflow: bool,
 
+        // how the top value ordered against the one below it at the last CMPu, CMPi or CMPf.
+        // None before the first compare, or after a float compare with a NaN
+        pub signal_compare: Option<Ordering>,
+
         // libs have no main function and cannot be run individually. Can be loaded and called later.
         pub is_lib: bool,
 
         // 16 bytes is sufficient to perform an operation on 2 64bit numbers.
         // essentially this is 2 64bit registers that can be operated on in chunks for
-        //smaller operations
+        //smaller operations. SETREG64 / GETREG64 move them to and from the stack
         pub registers: [u8; 16],
 
-        // 128but op registers: used to perform a 128bit operation. vm.registers form one 128 bit number (8*16) and vm._128bit_registers form the other
+        // 128bit op registers: used to perform a 128bit operation. vm.registers form one 128 bit number (8*16) and vm._128_registers form the other
         pub _128_registers: [u8; 16],
+
+        // host functions reachable through SYSCALL and SYSCALLD, keyed by syscall id
+        pub syscalls: HashMap<usize, SyscallHandler>,
+
+        // guest console output (the print syscalls), stdout unless replaced
+        pub console: Box<dyn Write>,
+
+        // debug traces printed when signal_debug is set, stdout unless replaced
+        pub trace: Box<dyn Write>,
+
+        // what each operation costs when running with run_for
+        pub fuel_costs: FuelCosts,
+
+        // the fuel left while an operation runs inside run_for, None otherwise. library calls run on it
+        pub(crate) fuel: Option<u64>,
+
+        // slots made by ENTER in every open frame. they count against limits.max_memory_words with vm.memory
+        pub(crate) local_words: usize,
+
+        // libraries loaded by LIBLOAD, kept between calls to run and run_for
+        pub libs: HashMap<String, StalDynamicLibrary>,
+
+        // maximum memory, stack and frame sizes the program may grow to
+        pub limits: Limits,
+
+        // <pc of the catch or finally, handler>, built by prepare
+        pub handlers: BTreeMap<usize, Handler>,
+
+        // the exception being handled, or the one that went uncaught
+        pub exception: Option<Exception>,
+
+        // if set, runtime faults (see VmError::fault_code) are thrown as guest exceptions instead of stopping the VM
+        pub trap_faults: bool,
+
+        // the program with its labels resolved, built by prepare. this is what actually runs
+        pub(crate) code: Rc<[Instruction]>,
+    }
+
+    ///
+    /// A frame opened by a jump. from is the program counter of the jump, to is where it landed.
+    /// allocations are the identifiers allocated while this was the innermost frame, which are
+    /// released when the frame is closed by RET or unwound by EXCEPT_THROW.
+    /// handling is the pc of the catch or finally whose block is running in this frame, until its
+    /// EXCEPT_END. exceptions thrown meanwhile only go to handlers before it.
+    /// locals are the slots made by ENTER, so each call of a recursive function has its own.
+    ///
+    #[derive(Debug, Clone)]
+    pub struct StackFrame {
+        pub from: usize,
+        pub to: usize,
+        pub allocations: Vec<usize>,
+        pub handling: Option<usize>,
+        pub locals: Vec<u32>,
     }
 
+    impl StackFrame {
+        pub fn new(from: usize, to: usize) -> StackFrame {
+            StackFrame {
+                from,
+                to,
+                allocations: vec![],
+                handling: None,
+                locals: vec![],
+            }
+        }
+    }
+
+    // the type a CONST_ operator stored an identifier as, which LOADf and LOADi convert from
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum ConstType {
+        Unsigned,
+        Float,
+        Signed,
+        Bool,
+        String,
+    }
+
+    ///
+    /// Ok(true) continues the program, Ok(false) finishes it (like the exit syscall) and an Err
+    /// stops the VM with that error.
+    ///
+    pub type SyscallResult = Result<bool, VmError>;
+
+    // a host function called with the VM and the args popped for the syscall, in the order they were pushed
+    pub type SyscallHandler = Box<dyn FnMut(&mut VM, Vec<u32>) -> SyscallResult>;
+
     impl VM {
         pub fn new() -> VM {
             VM {
@@ -65,112 +178,179 @@ pub mod stalfos {
 
                 //dict that maps a preset value to a memory address
                 static_alloc_table: BTreeMap::new(),
+                const_types: BTreeMap::new(),
                 program: vec![],
                 program_counter: 0,
                 signal_finished: false,
                 signal_debug: false,
                 signal_overflow: false,
+                signal_compare: None,
                 is_lib: false,
                 registers: [0; 16],
                 _128_registers: [0; 16],
-                dynamic_allocations: vec![],
+                dynamic_allocations: BTreeMap::new(),
+                allocator: Allocator::new(),
+                syscalls: VM::default_syscalls(),
+                console: Box::new(std::io::stdout()),
+                trace: Box::new(std::io::stdout()),
+                fuel_costs: FuelCosts::default(),
+                fuel: None,
+                local_words: 0,
+                libs: HashMap::new(),
+                limits: Limits::default(),
+                handlers: BTreeMap::new(),
+                exception: None,
+                trap_faults: false,
+                code: Rc::from(vec![]),
             }
         }
 
-        pub fn run_new(program: Vec<ops::Operator>) -> VM {
+        pub fn run_new(program: Vec<ops::Operator>) -> Result<VM, VmError> {
             let mut vm = VM::new();
-            vm.add_ops(program).prepare().run();
-            vm
+            vm.add_ops(program).prepare()?.run()?;
+            Ok(vm)
         }
 
-        pub fn run_new_debug(program: Vec<ops::Operator>) -> VM {
+        pub fn run_new_debug(program: Vec<ops::Operator>) -> Result<VM, VmError> {
             let mut vm = VM::new_debug();
-            vm.add_ops(program).prepare().run();
-            vm
+            vm.add_ops(program).prepare()?.run()?;
+            Ok(vm)
         }
 
         pub fn new_debug() -> VM {
             VM {
-                stack: vec![],
-                memory: vec![],
-                jmp_table: HashMap::new(),
-                stack_frame_pointers: vec![],
-                output: vec![],
-                //dict that maps a preset value to a memory address
-                static_alloc_table: BTreeMap::new(),
-                program: vec![],
-                program_counter: 0,
-                signal_finished: false,
                 signal_debug: true,
-                signal_overflow: false,
-                is_lib: false,
-                registers: [0; 16],
-                _128_registers: [0; 16],
-                dynamic_allocations: vec![],
+                ..VM::new()
             }
         }
 
-        pub fn execute_program(&mut self, program: Vec<ops::Operator>) -> &mut VM {
-            self.add_ops(program).prepare().run()
+        pub fn execute_program(&mut self, program: Vec<ops::Operator>) -> Result<&mut VM, VmError> {
+            self.add_ops(program).prepare()?.run()
+        }
+
+        pub fn run(&mut self) -> Result<&mut VM, VmError> {
+            let mut libs = std::mem::take(&mut self.libs);
+            let result = self.run_with_libs(libs.borrow_mut()).map(|_| ());
+            self.libs = libs;
+            result?;
+            Ok(self)
+        }
+
+        ///
+        /// Runs until the program finishes, faults, or the next operation costs more than the fuel
+        /// that is left (see fuel_costs). After OutOfFuel the VM can be resumed with another run_for.
+        ///
+        pub fn run_for(&mut self, fuel: u64) -> RunOutcome {
+            let mut libs = std::mem::take(&mut self.libs);
+            let mut remaining = fuel;
+            let outcome = self.run_metered(&mut libs, &mut remaining);
+            self.libs = libs;
+            outcome
         }
 
-        pub fn run(&mut self) -> &mut VM {
-            let mut libs = HashMap::new();
+        ///
+        /// The loop behind run_for, leaving what is left of fuel in it. A library called meanwhile
+        /// runs on the fuel that is left after its call site, and what it uses is taken from fuel.
+        ///
+        pub(crate) fn run_metered(
+            &mut self,
+            libs: &mut HashMap<String, StalDynamicLibrary>,
+            fuel: &mut u64,
+        ) -> RunOutcome {
             loop {
-                if !op_calls::op_calls::execute_operation(self,libs.borrow_mut()) {
-                    self.program_counter += 1;
+                if self.signal_finished {
+                    return RunOutcome::Finished;
                 }
 
-                if self.signal_debug {
-                    println!("{}", self.program_counter);
+                // a program counter outside of the program costs nothing, step reports it
+                let cost = match self.program.get(self.program_counter) {
+                    Some(op) => self.fuel_costs.cost_of(op),
+                    None => 0,
+                };
+                if cost > *fuel {
+                    return RunOutcome::OutOfFuel;
                 }
+                *fuel -= cost;
 
-                if self.signal_finished {
-                    break;
+                self.fuel = Some(*fuel);
+                let result = self.step(libs).map(|_| ());
+                if let Some(left) = self.fuel.take() {
+                    *fuel = left;
+                }
+                if let Err(error) = result {
+                    return RunOutcome::Trapped(error);
                 }
             }
-
-            return self;
         }
 
-        pub fn run_with_libs(&mut self, libs: &mut HashMap<String, StalDynamicLibrary>) -> &mut VM {
+        pub fn run_with_libs(
+            &mut self,
+            libs: &mut HashMap<String, StalDynamicLibrary>,
+        ) -> Result<&mut VM, VmError> {
             loop {
-                if !op_calls::op_calls::execute_operation(self, libs) {
-                    self.program_counter += 1;
-                }
-
-                if self.signal_debug {
-                    println!("{}", self.program_counter);
-                }
+                self.step(libs)?;
 
                 if self.signal_finished {
                     break;
                 }
             }
 
-            return self;
+            return Ok(self);
         }
 
-        pub fn run_specific_operation(&mut self, operation_number: usize) -> &mut VM {
+        ///
+        /// Executes the operation at the program counter and moves on to the next one, unless the
+        /// operation moved the program counter itself. Check signal_finished afterwards.
+        ///
+        pub fn step(
+            &mut self,
+            libs: &mut HashMap<String, StalDynamicLibrary>,
+        ) -> Result<&mut VM, VmError> {
+            let pc = self.program_counter;
+            let has_changed_ptr = match op_calls::op_calls::execute_operation(self, libs) {
+                Ok(has_changed_ptr) => has_changed_ptr,
+                Err(error) => {
+                    self.trap(error, pc)?;
+                    true
+                }
+            };
+            self.check_limits(pc)?;
+            if !has_changed_ptr {
+                self.program_counter += 1;
+            }
+
+            if self.signal_debug {
+                let pc = self.program_counter;
+                self.write_trace(format_args!("{}", pc))?;
+            }
+
+            return Ok(self);
+        }
+
+        pub fn run_specific_operation(&mut self, operation_number: usize) -> Result<&mut VM, VmError> {
             // let mut libL:HashMap<String,StalDynamicLibrary> = ;
 
             // let libraries = L.borrow_mut();
 
             let pc_before: usize = self.program_counter;
             self.program_counter = operation_number;
-            op_calls::op_calls::execute_operation(self, HashMap::new().borrow_mut());
+            let result = op_calls::op_calls::execute_operation(self, HashMap::new().borrow_mut());
             self.program_counter = pc_before;
-            self
+            result?;
+            Ok(self)
         }
 
-        pub fn run_single_operation(&mut self, op: Operator) -> &mut VM {
+        pub fn run_single_operation(&mut self, op: Operator) -> Result<&mut VM, VmError> {
             let pc_before: usize = self.program_counter;
+            let instruction = Instruction::new(&op, &self.jmp_table);
+            // pushed so errors can report the operator, but run without lowering the whole program again
             self.program.push(op);
             self.program_counter = self.program.len() - 1;
-            op_calls::op_calls::execute_operation(self, HashMap::new().borrow_mut());
+            let result = op_calls::op_calls::execute_instruction(self, &instruction, HashMap::new().borrow_mut());
             self.program.pop();
             self.program_counter = pc_before;
-            self
+            result?;
+            Ok(self)
         }
 
         pub fn add_op(&mut self, op: ops::Operator) -> &mut VM {
@@ -216,53 +396,126 @@ pub mod stalfos {
         }
 
         /**
-         * Sets up jump table, finds main and sets the program counter to it
+         * Sets up jump table and exception handlers, finds main and sets the program counter to it
          * @param preset_value
          * @param location
          * @param size
          */
-        pub fn prepare(&mut self) -> &mut VM {
+        pub fn prepare(&mut self) -> Result<&mut VM, VmError> {
             self.process_jump_definitions();
+            self.handlers = handler_table(&self.program, &self.jmp_table)?;
+            self.code = lower(&self.program, &self.jmp_table).into();
             self.program_counter = 0;
 
             if self.jmp_table.contains_key("main") {
                 self.program_counter = self.jmp_table["main"];
-                self.stack_frame_pointers.push((0, self.program_counter))
+                self.stack_frame_pointers.push(StackFrame::new(0, self.program_counter))
             } else {
                 if !self.is_lib {
-                    panic!("No main function found");
+                    return Err(VmError::NoMain);
                 }
             }
 
-            return self;
+            return Ok(self);
         }
 
-        pub(crate) fn syscall(syscall_id: usize, mut args: Vec<u32>) -> bool {
+        ///
+        /// The built-in syscalls a new VM starts with. Embedders can replace or remove any of them
+        /// with register_syscall and remove_syscall.
+        ///
+        /// 0: panic, stops the VM with a GuestPanic carrying the first arg as its code
+        /// 1: prints the first arg
+        /// 2: exit, finishes the program
+        /// 3: prints the args as a string
+        ///
+        pub fn default_syscalls() -> HashMap<usize, SyscallHandler> {
+            let mut syscalls: HashMap<usize, SyscallHandler> = HashMap::new();
+
             //if you do not have arguments and the syscall requires an argument, a 1 represents false
-            if args.is_empty() {
-                args.push(1)
-            }
-            match syscall_id {
-                0 => {
-                    panic!("VM called a panic! with code {}", args[0]);
-                }
-                1 => {
-                    println!("{}", args[0]);
-                }
-                2 => {
-                    println!("VM ended with exit code {}", args[0]);
-                    return false;
-                }
-                3 => {
-                    let string = VM::get_string_from_u32_vec(args);
-                    println!("{}", string);
-                }
-                _ => {
-                    println!("Unknown syscall: {}", syscall_id);
+            syscalls.insert(
+                0,
+                Box::new(|vm, args| {
+                    Err(VmError::GuestPanic {
+                        pc: vm.program_counter,
+                        op: Box::new(vm.current_operator()?),
+                        code: args.first().copied().unwrap_or(1),
+                    })
+                }),
+            );
+            syscalls.insert(
+                1,
+                Box::new(|vm, args| {
+                    vm.write_console(format_args!("{}", args.first().copied().unwrap_or(1)))?;
+                    Ok(true)
+                }),
+            );
+            syscalls.insert(
+                2,
+                Box::new(|vm, args| {
+                    let code = args.first().copied().unwrap_or(1);
+                    vm.write_console(format_args!("VM ended with exit code {}", code))?;
+                    Ok(false)
+                }),
+            );
+            syscalls.insert(
+                3,
+                Box::new(|vm, args| {
+                    vm.write_console(format_args!("{}", VM::get_string_from_u32_vec(args)))?;
+                    Ok(true)
+                }),
+            );
+            syscalls.insert(
+                4,
+                Box::new(|vm, args| {
+                    let bits = args.first().copied().unwrap_or(0);
+                    vm.write_console(format_args!("{}", f32::from_bits(bits)))?;
+                    Ok(true)
+                }),
+            );
+
+            syscalls
+        }
+
+        ///
+        /// Makes a host function available to SYSCALL and SYSCALLD under syscall_id, replacing
+        /// (and returning) any handler already registered for it.
+        ///
+        pub fn register_syscall(
+            &mut self,
+            syscall_id: usize,
+            handler: SyscallHandler,
+        ) -> Option<SyscallHandler> {
+            self.syscalls.insert(syscall_id, handler)
+        }
+
+        pub fn remove_syscall(&mut self, syscall_id: usize) -> Option<SyscallHandler> {
+            self.syscalls.remove(&syscall_id)
+        }
+
+        // runs the handler for syscall_id. Ok(false) means the program should finish
+        pub(crate) fn call_syscall(
+            &mut self,
+            syscall_id: usize,
+            args: Vec<u32>,
+        ) -> Result<bool, VmError> {
+            // the handler is taken out of the table while it runs so it can borrow the VM mutably
+            let mut handler = match self.syscalls.remove(&syscall_id) {
+                Some(handler) => handler,
+                None => {
+                    return Err(VmError::UnknownSyscall {
+                        pc: self.program_counter,
+                        op: Box::new(self.current_operator()?),
+                        syscall_id,
+                    })
                 }
-            }
+            };
+
+            let result = handler(self, args);
 
-            return true;
+            // a handler that registered a replacement for its own id while running is not put back
+            self.syscalls.entry(syscall_id).or_insert(handler);
+
+            result
         }
 
         pub fn get_string_from_u32_vec(values: Vec<u32>) -> String {
@@ -275,11 +528,8 @@ pub mod stalfos {
             }
 
             //remove trailing null chars
-            let mut index = string.len() - 1;
-            while string.chars().nth(index).unwrap() == '\0' {
-                index -= 1;
-            }
-            string.truncate(index + 1);
+            let trimmed_length = string.trim_end_matches('\0').len();
+            string.truncate(trimmed_length);
 
             return string;
         }
@@ -295,85 +545,267 @@ pub mod stalfos {
         /// returns: usize : the pointer to the allocated memory in the heap. Indexing the heap with this value+0 -> value+size-1 are valid for this allocation
         ///
 
-        pub fn allocate(&mut self, ptr: usize, size: u32) -> usize {
-            // fn _alloc(vm: &mut VM, ptr: &mut usize, size: &mut u32) -> usize {
-            let table = self.static_alloc_table.borrow_mut();
-
-            //get all keys as a vector
-            let keys: Vec<usize> = table.keys().map(|x| *x).collect();
-            let l = keys.len();
-            for x in 0..l {
-                let current_pointer = keys.get(x).unwrap();
-                let opt_next_pointer = keys.get(x + 1);
-                let has_next = opt_next_pointer.is_some();
-                // if the current key is not the final one in the allocations, check if the required size fits between current+len and next
-                if x < l && has_next {
-                    let next_pointer = keys.get(x + 1).unwrap();
-                    let v = self.static_alloc_table[&current_pointer];
-                    let (stack_location, s) = v;
-                    let (next_stack_location, _) = self.static_alloc_table[&next_pointer];
-                    if stack_location + s as usize + (size as usize) < next_stack_location {
-                        let allocation = (stack_location, s + size);
-                        self.static_alloc_table.insert(ptr, allocation);
-                        return stack_location;
-                    }
+        pub fn allocate(&mut self, ptr: usize, size: u32) -> Result<usize, VmError> {
+            let location = self.allocate_persistent(ptr, size)?;
+            self.own_allocation(ptr);
+
+            return Ok(location);
+        }
+
+        // the innermost frame releases ptr again when it returns or is unwound
+        pub(crate) fn own_allocation(&mut self, ptr: usize) {
+            if let Some(frame) = self.stack_frame_pointers.last_mut() {
+                frame.allocations.push(ptr);
+            }
+        }
+
+        ///
+        /// Allocates like allocate, but the allocation is not owned by the current stack frame so
+        /// it outlives the function that made it. It lasts until it is deallocated or its
+        /// identifier is allocated again.
+        ///
+        pub fn allocate_persistent(&mut self, ptr: usize, size: u32) -> Result<usize, VmError> {
+            // allocating an identifier again gives up the memory it had before
+            self.deallocate(ptr);
+
+            let location = self.heap_allocate(size)?;
+            self.static_alloc_table.insert(ptr, (location, size));
+
+            return Ok(location);
+        }
+
+        ///
+        /// Allocates size words addressed by their location rather than an identifier, for heap
+        /// pointers. An empty allocation still takes a word of memory so every pointer is unique.
+        ///
+        pub fn dyn_allocate(&mut self, size: u32) -> Result<usize, VmError> {
+            let location = self.heap_allocate(size.max(1))?;
+            self.dynamic_allocations.insert(location, size);
+
+            return Ok(location);
+        }
+
+        // frees the heap allocation at location, returning false if there was none
+        pub fn dyn_deallocate(&mut self, location: usize) -> bool {
+            match self.dynamic_allocations.remove(&location) {
+                Some(size) => self.allocator.free(location, size.max(1) as usize),
+                None => false,
+            }
+        }
+
+        // frees the allocation for the identifier ptr, returning false if there was none
+        pub fn deallocate(&mut self, ptr: usize) -> bool {
+            match self.detach_allocation(ptr) {
+                Some((location, size)) => self.allocator.free(location, size as usize),
+                None => false,
+            }
+        }
+
+        // removes ptr from the static_alloc_table without freeing its memory, returning its (location, size)
+        pub(crate) fn detach_allocation(&mut self, ptr: usize) -> Option<(usize, u32)> {
+            let allocation = self.static_alloc_table.remove(&ptr)?;
+            self.const_types.remove(&ptr);
+
+            // the frame that owned it must not release the identifier again once it is reused
+            for frame in self.stack_frame_pointers.iter_mut().rev() {
+                if let Some(i) = frame.allocations.iter().position(|owned| *owned == ptr) {
+                    frame.allocations.swap_remove(i);
+                    break;
                 }
             }
 
-            let end_of_stack = self.memory.len();
-            let allocation = (end_of_stack, size);
-            self.static_alloc_table.insert(ptr, allocation);
-            for _ in 0..size {
-                self.memory.push(0);
+            Some(allocation)
+        }
+
+        ///
+        /// Closes the innermost stack frame, deallocating everything allocated in it with ALLOC,
+        /// DALLOC, POPS or a CONST. ALLOC_PERSIST allocations and heap pointers from DNEW are left
+        /// alone.
+        ///
+        pub fn pop_frame(&mut self) -> Option<StackFrame> {
+            let frame = self.stack_frame_pointers.pop()?;
+            self.local_words -= frame.locals.len();
+            for ptr in frame.allocations.iter() {
+                if let Some((location, size)) = self.static_alloc_table.remove(ptr) {
+                    self.allocator.free(location, size as usize);
+                    self.const_types.remove(ptr);
+                }
+            }
+            Some(frame)
+        }
+
+        ///
+        /// Unwinds to the nearest handler for the exception that comes before the program counter
+        /// before, first in the innermost frame and then before the call site in each caller. Frames that are
+        /// left are closed, releasing their allocations. Returns Uncaught once no frames are left.
+        ///
+        pub(crate) fn throw(&mut self, mut exception: Exception, before: usize) -> Result<(), VmError> {
+            // a payload the previous exception's handler did not take is freed
+            if let Some(Exception { payload: Some((location, size)), .. }) = self.exception.take() {
+                self.allocator.free(location, size as usize);
             }
 
-            return end_of_stack;
+            let (depth, pc, handler) = match self.find_handler(exception.code, before) {
+                Some(found) => found,
+                None => {
+                    while self.pop_frame().is_some() {}
+                    let (code, pc) = (exception.code, exception.pc);
+                    self.exception = Some(exception);
+                    return Err(VmError::Uncaught { code, pc });
+                }
+            };
 
+            // the frames above the one with the handler are left
+            while self.stack_frame_pointers.len() > depth + 1 {
+                self.pop_frame();
+            }
 
+            exception.finally_at = match handler.kind {
+                HandlerKind::Finally => Some(pc),
+                HandlerKind::Catch { .. } => None,
+            };
+            if self.signal_debug {
+                self.write_trace(format_args!("CATCH FOUND: {},{}", pc, handler.target))?;
+            }
+            self.exception = Some(exception);
+            self.program_counter = handler.target;
+            if let Some(frame) = self.stack_frame_pointers.last_mut() {
+                frame.handling = Some(pc);
+            }
+            Ok(())
         }
 
-        pub fn dyn_allocate(&mut self, size:u32) -> usize {
-            let table = self.static_alloc_table.borrow_mut();
-            //get all keys as a vector
-            let keys: Vec<usize> = table.keys().map(|x| *x).collect();
-            let l = keys.len();
-            for x in 0..l {
-                let current_pointer = keys.get(x).unwrap();
-                let opt_next_pointer = keys.get(x + 1);
-                let has_next = opt_next_pointer.is_some();
-                // if the current key is not the final one in the allocations, check if the required size fits between current+len and next
-                if x < l && has_next {
-                    let next_pointer = keys.get(x + 1).unwrap();
-                    let v = self.static_alloc_table[&current_pointer];
-                    let (stack_location, s) = v;
-                    let (next_stack_location, _) = self.static_alloc_table[&next_pointer];
-                    if stack_location + s as usize + (size as usize) < next_stack_location {
-                        let allocation = (stack_location, s + size);
-                        self.dynamic_allocations.push(allocation);
-                        return stack_location;
+        // finds the handler throw would unwind to, as the index of its frame, its pc and the handler
+        fn find_handler(&self, code: u32, before: usize) -> Option<(usize, usize, Handler)> {
+            let mut before = before;
+            for (depth, frame) in self.stack_frame_pointers.iter().enumerate().rev() {
+                let (start, end) = (frame.to, frame.handling.map_or(before, |pc| pc.min(before)));
+                if start < end {
+                    let handler = self
+                        .handlers
+                        .range(start..end)
+                        .rev()
+                        .find(|(_, handler)| handler.handles(code));
+                    if let Some((pc, handler)) = handler {
+                        return Some((depth, *pc, *handler));
                     }
                 }
+
+                // not handled in this frame, carry on from the call that opened it
+                before = frame.from;
             }
+            None
+        }
+
+        ///
+        /// With trap_faults set, throws a fault raised by the operation at pc as an exception with
+        /// its fault code. Whatever the operation popped before failing stays popped. Gives the
+        /// error back if it is not trapped, or if nothing catches it, without unwinding any frames.
+        ///
+        fn trap(&mut self, error: VmError, pc: usize) -> Result<(), VmError> {
+            let code = match error.fault_code() {
+                Some(code) if self.trap_faults => code,
+                _ => return Err(error),
+            };
+
+            // an uncaught fault leaves the frames as they were, for the embedder to look at
+            if self.find_handler(code, pc).is_none() {
+                return Err(error);
+            }
+            self.throw(Exception::new(code, None, pc), pc)
+        }
+
+        ///
+        /// The program the interpreter runs. Operators added since prepare (eg with add_ops) are
+        /// lowered again here, an operator replaced in place is not picked up until prepare is
+        /// called again.
+        ///
+        pub(crate) fn lowered(&mut self) -> Rc<[Instruction]> {
+            if self.code.len() != self.program.len() {
+                self.code = lower(&self.program, &self.jmp_table).into();
+            }
+            Rc::clone(&self.code)
+        }
+
+        // 64 bit register 0 or 1, big-endian in vm.registers
+        pub fn register64(&self, register: usize) -> u64 {
+            let bytes = &self.registers[register * 8..register * 8 + 8];
+            u64::from_be_bytes(bytes.try_into().unwrap())
+        }
+
+        pub fn set_register64(&mut self, register: usize, value: u64) {
+            self.registers[register * 8..register * 8 + 8].copy_from_slice(&value.to_be_bytes());
+        }
+
+        // 128 bit register 0 (vm.registers) or 1 (vm._128_registers), big-endian
+        pub fn register128(&self, register: usize) -> u128 {
+            match register {
+                0 => u128::from_be_bytes(self.registers),
+                _ => u128::from_be_bytes(self._128_registers),
+            }
+        }
 
-            let end_of_stack = self.memory.len();
-            let allocation = (end_of_stack, size);
-            self.dynamic_allocations.push(allocation);
-            for _ in 0..size {
-                self.memory.push(0);
+        pub fn set_register128(&mut self, register: usize, value: u128) {
+            match register {
+                0 => self.registers = value.to_be_bytes(),
+                _ => self._128_registers = value.to_be_bytes(),
             }
+        }
 
-            return end_of_stack;
+        // takes size words from the allocator, or a MemoryLimit error if memory cannot grow that far
+        fn heap_allocate(&mut self, size: u32) -> Result<usize, VmError> {
+            let limit = self.limits.max_memory_words;
+            // memory can only grow into what the locals of the open frames leave of the limit
+            let max_words = limit.saturating_sub(self.local_words);
+            match self.allocator.allocate(size as usize, &mut self.memory, max_words) {
+                Some(location) => Ok(location),
+                None => Err(VmError::MemoryLimit {
+                    pc: self.program_counter,
+                    op: Box::new(self.current_operator()?),
+                    requested: size as usize,
+                    limit,
+                }),
+            }
+        }
 
+        // checked after the operation at pc has run, so a single operation can briefly go over
+        fn check_limits(&self, pc: usize) -> Result<(), VmError> {
+            let op = || match self.program.get(pc) {
+                Some(op) => Ok(op.clone()),
+                None => Err(VmError::InvalidProgramCounter { pc }),
+            };
+
+            if self.stack.len() > self.limits.max_stack_depth {
+                return Err(VmError::StackOverflow {
+                    pc,
+                    op: Box::new(op()?),
+                    limit: self.limits.max_stack_depth,
+                });
+            }
+            if self.stack_frame_pointers.len() > self.limits.max_frame_depth {
+                return Err(VmError::FrameOverflow {
+                    pc,
+                    op: Box::new(op()?),
+                    limit: self.limits.max_frame_depth,
+                });
+            }
+            Ok(())
         }
 
-        pub fn get_next_string(&mut self) -> String {
-            let n_args = self.stack.pop().unwrap();
+        pub fn get_next_string(&mut self) -> Result<String, VmError> {
+            let n_args = match self.stack.pop() {
+                Some(n) => n,
+                None => return Err(self.stack_underflow()),
+            };
             let mut args = Vec::new();
             for _ in 0..n_args {
-                args.push(self.stack.pop().unwrap());
+                match self.stack.pop() {
+                    Some(v) => args.push(v),
+                    None => return Err(self.stack_underflow()),
+                }
             }
             args.reverse();
-            VM::get_string_from_u32_vec(args)
+            Ok(VM::get_string_from_u32_vec(args))
         }
 
         pub fn call_dynamic_library(
@@ -381,14 +813,77 @@ pub mod stalfos {
             libraries: &mut HashMap<String, StalDynamicLibrary>,
             library: String,
             label: String,
-        ) {
-            if libraries.contains_key(&*library) {
-                let lib = libraries.get(&*library).unwrap();
-                let mut invocation = StalDynamicInvocation::new(lib.clone());
-                let results = invocation.call_func(label, self.stack.clone(), libraries);
-                self.stack.extend(results);
-            } else {
-                panic!("Library {} not loaded", library);
+        ) -> Result<(), VmError> {
+            let lib = match libraries.get(&*library) {
+                Some(lib) => lib.clone(),
+                None => {
+                    return Err(VmError::UnknownLibrary {
+                        pc: self.program_counter,
+                        op: Box::new(self.current_operator()?),
+                        library,
+                    })
+                }
+            };
+
+            if !lib.jump_table.contains_key(&*label) {
+                return Err(VmError::UnknownLabel {
+                    pc: self.program_counter,
+                    op: Box::new(self.current_operator()?),
+                    label,
+                });
+            }
+
+            let mut invocation = StalDynamicInvocation::new(lib);
+            let arg_stack = self.stack.clone();
+            match invocation.call_func(label, arg_stack, libraries, self) {
+                Ok(results) => {
+                    self.stack.extend(results);
+                    Ok(())
+                }
+                Err(error) => Err(VmError::LibraryFault {
+                    pc: self.program_counter,
+                    op: Box::new(self.current_operator()?),
+                    library,
+                    error: Box::new(error),
+                }),
+            }
+        }
+
+        // writes a line to the console sink, for syscalls that print
+        pub fn write_console(&mut self, line: fmt::Arguments) -> Result<(), VmError> {
+            let result = writeln!(self.console, "{}", line).and_then(|_| self.console.flush());
+            result.map_err(|error| VmError::Output {
+                pc: self.program_counter,
+                message: error.to_string(),
+            })
+        }
+
+        // writes a line to the trace sink
+        pub fn write_trace(&mut self, line: fmt::Arguments) -> Result<(), VmError> {
+            let result = writeln!(self.trace, "{}", line).and_then(|_| self.trace.flush());
+            result.map_err(|error| VmError::Output {
+                pc: self.program_counter,
+                message: error.to_string(),
+            })
+        }
+
+        // the operator at the program counter, used to describe where a fault happened
+        fn current_operator(&self) -> Result<Operator, VmError> {
+            match self.program.get(self.program_counter) {
+                Some(op) => Ok(op.clone()),
+                None => Err(VmError::InvalidProgramCounter {
+                    pc: self.program_counter,
+                }),
+            }
+        }
+
+        fn stack_underflow(&self) -> VmError {
+            match self.current_operator() {
+                Ok(op) => VmError::StackUnderflow {
+                    pc: self.program_counter,
+                    op: Box::new(op),
+                },
+                Err(error) => error,
             }
         }
     }
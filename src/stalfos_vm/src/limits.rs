@@ -0,0 +1,37 @@
+pub mod limits {
+    ///
+    /// Caps on how much a guest program can grow the VM. Crossing one stops the VM with a
+    /// MemoryLimit, StackOverflow or FrameOverflow error instead of exhausting the host.
+    ///
+    #[derive(Debug, Clone, Copy)]
+    pub struct Limits {
+        // words in vm.memory plus the ENTER slots of every open frame, checked before anything is allocated
+        pub max_memory_words: usize,
+        // values on vm.stack, checked after every operation
+        pub max_stack_depth: usize,
+        // entries in vm.stack_frame_pointers, checked after every operation
+        pub max_frame_depth: usize,
+    }
+
+    impl Limits {
+        // no limits at all, for trusted programs
+        pub fn unlimited() -> Limits {
+            Limits {
+                max_memory_words: usize::MAX,
+                max_stack_depth: usize::MAX,
+                max_frame_depth: usize::MAX,
+            }
+        }
+    }
+
+    impl Default for Limits {
+        // 64MiB of memory, 4MiB of stack and a million frames
+        fn default() -> Limits {
+            Limits {
+                max_memory_words: 1 << 24,
+                max_stack_depth: 1 << 20,
+                max_frame_depth: 1 << 20,
+            }
+        }
+    }
+}
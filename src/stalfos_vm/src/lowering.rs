@@ -0,0 +1,55 @@
+pub mod lowering {
+    use crate::ops::ops::Operator;
+    use std::collections::HashMap;
+
+    ///
+    /// An operator with its label operands resolved to program counters, which is what the
+    /// interpreter executes. targets follow the order of the label operands (only JMPs and BRs have
+    /// a second one) and are None for a label that is not in the jmp table, which is an
+    /// UnknownLabel error if the jump is taken.
+    ///
+    #[derive(Debug, Clone)]
+    pub struct Instruction {
+        pub op: Operator,
+        pub targets: [Option<usize>; 2],
+    }
+
+    impl Instruction {
+        pub fn new(op: &Operator, jmp_table: &HashMap<String, usize>) -> Instruction {
+            let target = |label: &String| jmp_table.get(label).copied();
+            let targets = match op {
+                Operator::JMP(label)
+                | Operator::JMPo(label)
+                | Operator::JMPe(label)
+                | Operator::JMPne(label)
+                | Operator::CALL(label)
+                | Operator::BR(label)
+                | Operator::BRo(label)
+                | Operator::BRe(label)
+                | Operator::BRne(label)
+                | Operator::JMPlt(label)
+                | Operator::JMPgt(label)
+                | Operator::JMPle(label)
+                | Operator::JMPge(label)
+                | Operator::BRlt(label)
+                | Operator::BRgt(label)
+                | Operator::BRle(label)
+                | Operator::BRge(label) => [target(label), None],
+                Operator::JMPs(left, right) | Operator::BRs(left, right) => {
+                    [target(left), target(right)]
+                }
+                _ => [None, None],
+            };
+
+            Instruction {
+                op: op.clone(),
+                targets,
+            }
+        }
+    }
+
+    // lowers a whole program against the jmp table prepare() built
+    pub fn lower(program: &[Operator], jmp_table: &HashMap<String, usize>) -> Vec<Instruction> {
+        program.iter().map(|op| Instruction::new(op, jmp_table)).collect()
+    }
+}
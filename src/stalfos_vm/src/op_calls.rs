@@ -1,51 +1,88 @@
 pub mod op_calls {
     use crate::stal_dll::stal_dll;
     use crate::stal_dll::stal_dll::StalDynamicLibrary;
+    use crate::lowering::lowering::Instruction;
     use crate::stalfos::ops::Operator;
-    use crate::stalfos::VM;
-    use std::borrow::{Borrow, BorrowMut};
+    use crate::stalfos::{ConstType, Exception, StackFrame, VM};
+    use crate::vm_error::vm_error::VmError;
+    use std::cmp::Ordering;
     use std::collections::HashMap;
 
     pub fn execute_operation(
         vm: &mut crate::stalfos::VM,
         loaded_libs: &mut HashMap<String, StalDynamicLibrary>,
-    ) -> bool {
-        let op = vm.program[vm.program_counter].clone();
+    ) -> Result<bool, VmError> {
+        // shared, so the instruction can be borrowed while the vm changes
+        let code = vm.lowered();
+        match code.get(vm.program_counter) {
+            Some(instruction) => execute_instruction(vm, instruction, loaded_libs),
+            None => Err(VmError::InvalidProgramCounter {
+                pc: vm.program_counter,
+            }),
+        }
+    }
+
+    pub fn execute_instruction(
+        vm: &mut crate::stalfos::VM,
+        instruction: &Instruction,
+        loaded_libs: &mut HashMap<String, StalDynamicLibrary>,
+    ) -> Result<bool, VmError> {
+        let op = &instruction.op;
+        let targets = instruction.targets;
         let mut has_changed_ptr = false;
         let mut overflow = false;
         match op {
             Operator::PUSH(v) => {
-                vm.stack.push(v);
+                vm.stack.push(*v);
             }
             // LOAD only gets the first word (4bytes) of the allocation
             // this means it is only suitable for small allocations
             // use LOADD for larger allocations
             Operator::LOAD(ptr) => {
-                let v = vm.static_alloc_table[&ptr];
-                let (stack_location, _) = v;
-                let val = vm.memory[stack_location];
+                let (stack_location, _) = allocation(vm, op, *ptr)?;
+                let val = read_memory(vm, op, stack_location)?;
                 vm.stack.push(val);
             }
             Operator::LOADD(ptr) => {
-                let v = vm.static_alloc_table[&ptr];
-                let (stack_location, size) = v;
+                let (stack_location, size) = allocation(vm, op, *ptr)?;
                 let size = size as usize;
                 for i in 0..size {
-                    let val = vm.memory[stack_location + i];
+                    let val = read_memory(vm, op, stack_location + i)?;
                     vm.stack.push(val);
                 }
 
                 vm.stack.push(size as u32);
             }
+            Operator::LOADf(ptr) => {
+                let (location, _) = allocation(vm, op, *ptr)?;
+                let val = read_memory(vm, op, location)?;
+                let val = match vm.const_types.get(ptr) {
+                    Some(ConstType::Unsigned) | Some(ConstType::Bool) => f_to_u(val as f32),
+                    Some(ConstType::Signed) => f_to_u(u_to_i(val) as f32),
+                    _ => val,
+                };
+                vm.stack.push(val);
+            }
+            Operator::LOADi(ptr) => {
+                let (location, _) = allocation(vm, op, *ptr)?;
+                let val = read_memory(vm, op, location)?;
+                let val = match vm.const_types.get(ptr) {
+                    // truncated towards 0, saturating at the ends of the i32 range (NaN is 0)
+                    Some(ConstType::Float) => i_to_u(u_to_f(val) as i32),
+                    _ => val,
+                };
+                vm.stack.push(val);
+            }
             Operator::CONST_U(identifier, value_to_store) => {
                 let size = 1;
-                let allocated_memory_location = vm.allocate(identifier, size);
-                vm.memory[allocated_memory_location] = value_to_store;
+                let allocated_memory_location = vm.allocate(*identifier, size)?;
+                vm.memory[allocated_memory_location] = *value_to_store;
+                vm.const_types.insert(*identifier, ConstType::Unsigned);
             }
             Operator::CONST_F(ptr, v) => {
-                let v = v as u32;
-                vm.static_alloc_table.insert(ptr, (vm.memory.len(), v));
-                vm.memory.push(v as u32);
+                let location = vm.allocate(*ptr, 1)?;
+                vm.memory[location] = v.to_bits();
+                vm.const_types.insert(*ptr, ConstType::Float);
             }
             Operator::CONST_S(ptr, string) => {
                 //split string into byte chunks
@@ -75,202 +112,268 @@ pub mod op_calls {
 
                 let size = string_chunks.len() as u32;
                 //store the chunks in the memory
-                let allocated_memory_location = vm.allocate(ptr, size);
+                let allocated_memory_location = vm.allocate(*ptr, size)?;
 
                 for i in 0..string_chunks.len() {
                     vm.memory[allocated_memory_location + i] = string_chunks[i];
                 }
+                vm.const_types.insert(*ptr, ConstType::String);
             }
             Operator::CONST_I(ptr, v) => {
-                let v = v as u32;
-                vm.static_alloc_table.insert(ptr, (vm.memory.len(), v));
-                vm.memory.push(v);
+                let location = vm.allocate(*ptr, 1)?;
+                vm.memory[location] = *v as u32;
+                vm.const_types.insert(*ptr, ConstType::Signed);
             }
             Operator::CONST_B(ptr, v) => {
-                let val = if v { 1 } else { 0 };
-                vm.static_alloc_table.insert(ptr, (vm.memory.len(), val));
-                vm.memory.push(val);
+                let val = if *v { 1 } else { 0 };
+                let location = vm.allocate(*ptr, 1)?;
+                vm.memory[location] = val;
+                vm.const_types.insert(*ptr, ConstType::Bool);
             }
             Operator::LOAD_CONST(ptr) => {
-                let v = vm.static_alloc_table[&ptr];
-                let (stack_location, _) = v;
-                let val = vm.memory[stack_location];
+                let (stack_location, _) = allocation(vm, op, *ptr)?;
+                let val = read_memory(vm, op, stack_location)?;
                 vm.stack.push(val);
             }
             Operator::GETLEN(ptr) => {
-                let v = vm.static_alloc_table[&ptr];
-                let (_, s) = v;
+                let (_, s) = allocation(vm, op, *ptr)?;
                 vm.stack.push(s);
             }
 
             Operator::POP => {
-                vm.stack.pop();
+                pop(vm, op)?;
             }
             Operator::ALLOC(ptr, size) => {
-                //check for allocation gaps in the stack
-                let table = vm.static_alloc_table.borrow_mut();
-
-                //get all keys as a vector
-                let keys: Vec<usize> = table.keys().map(|x| *x).collect();
-                let l = keys.len();
-                for x in 0..l {
-                    // if the current key is not the final one in the allocations, check if the required size fits between current+len and next
-                    if x < l {
-                        let sptr = keys.get(x).unwrap();
-                        let next = keys.get(x + 1).unwrap();
-                        let v = vm.static_alloc_table[&sptr];
-                        let (stack_location, s) = v;
-                        let (next_stack_location, _) = vm.static_alloc_table[&next];
-                        if stack_location + s as usize + (size as usize) < next_stack_location {
-                            let allocation = (stack_location, s + size);
-                            let p = ptr;
-                            vm.static_alloc_table.insert(p, allocation);
-                        }
-                    }
-                }
-
-                let p = vm.memory.len();
-                let allocation = (p, size);
-                vm.static_alloc_table.insert(ptr, allocation);
-                for _ in 0..size {
-                    vm.memory.push(0);
-                }
+                vm.allocate(*ptr, *size)?;
+            }
+            Operator::ALLOC_PERSIST(ptr, size) => {
+                vm.allocate_persistent(*ptr, *size)?;
             }
             Operator::POPS(ptr) => {
                 //pop and store
-                let v = vm.stack.pop().unwrap();
-                vm.static_alloc_table.insert(ptr, (vm.memory.len(), v));
-                vm.memory.push(v);
+                let v = pop(vm, op)?;
+                let location = vm.allocate(*ptr, 1)?;
+                vm.memory[location] = v;
             }
             Operator::ADDf => {
-                let a = u_to_f(vm.stack.pop().unwrap());
-                let b = u_to_f(vm.stack.pop().unwrap());
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
                 vm.stack.push(f_to_u(a + b));
             }
             Operator::SUBf => {
-                let a = u_to_f(vm.stack.pop().unwrap());
-                let b = u_to_f(vm.stack.pop().unwrap());
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
                 vm.stack.push(f_to_u(a - b));
             }
             Operator::MULf => {
-                let a = u_to_f(vm.stack.pop().unwrap());
-                let b = u_to_f(vm.stack.pop().unwrap());
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
                 vm.stack.push(f_to_u(a * b));
             }
             Operator::DIVf => {
-                let a = u_to_f(vm.stack.pop().unwrap());
-                let b = u_to_f(vm.stack.pop().unwrap());
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
                 vm.stack.push(f_to_u(a / b));
             }
             Operator::MODf => {
-                let a = u_to_f(vm.stack.pop().unwrap());
-                let b = u_to_f(vm.stack.pop().unwrap());
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
                 vm.stack.push(f_to_u(a % b));
             }
+            Operator::ITOF => {
+                let v = u_to_i(pop(vm, op)?);
+                vm.stack.push(f_to_u(v as f32));
+            }
+            Operator::FTOI => {
+                let v = u_to_f(pop(vm, op)?);
+                vm.stack.push(i_to_u(v as i32));
+            }
+            Operator::UTOF => {
+                let v = pop(vm, op)?;
+                vm.stack.push(f_to_u(v as f32));
+            }
+            Operator::SQRTf => {
+                let v = u_to_f(pop(vm, op)?);
+                vm.stack.push(f_to_u(v.sqrt()));
+            }
+            Operator::ABSf => {
+                let v = u_to_f(pop(vm, op)?);
+                vm.stack.push(f_to_u(v.abs()));
+            }
+            Operator::FLOORf => {
+                let v = u_to_f(pop(vm, op)?);
+                vm.stack.push(f_to_u(v.floor()));
+            }
+            Operator::CEILf => {
+                let v = u_to_f(pop(vm, op)?);
+                vm.stack.push(f_to_u(v.ceil()));
+            }
+            Operator::MINf => {
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
+                vm.stack.push(f_to_u(a.min(b)));
+            }
+            Operator::MAXf => {
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
+                vm.stack.push(f_to_u(a.max(b)));
+            }
+            Operator::POWf => {
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
+                vm.stack.push(f_to_u(a.powf(b)));
+            }
             Operator::ADDi => {
-                let a = u_to_i(vm.stack.pop().unwrap());
-                let b = u_to_i(vm.stack.pop().unwrap());
+                let a = u_to_i(pop(vm, op)?);
+                let b = u_to_i(pop(vm, op)?);
                 let (v, o) = i32::overflowing_add(a, b);
                 vm.stack.push(i_to_u(v));
                 overflow = o;
             }
             Operator::SUBi => {
-                let a = u_to_i(vm.stack.pop().unwrap());
-                let b = u_to_i(vm.stack.pop().unwrap());
+                let a = u_to_i(pop(vm, op)?);
+                let b = u_to_i(pop(vm, op)?);
                 let (v, o) = i32::overflowing_sub(a, b);
                 vm.stack.push(i_to_u(v));
                 overflow = o;
             }
             Operator::MULi => {
-                let a = u_to_i(vm.stack.pop().unwrap());
-                let b = u_to_i(vm.stack.pop().unwrap());
+                let a = u_to_i(pop(vm, op)?);
+                let b = u_to_i(pop(vm, op)?);
                 let (v, o) = i32::overflowing_mul(a, b);
                 vm.stack.push(i_to_u(v));
                 overflow = o;
             }
             Operator::DIVi => {
-                let a = u_to_i(vm.stack.pop().unwrap());
-                let b = u_to_i(vm.stack.pop().unwrap());
+                let a = u_to_i(pop(vm, op)?);
+                let b = u_to_i(pop(vm, op)?);
+                if b == 0 {
+                    return Err(divide_by_zero(vm, op));
+                }
                 let (v, o) = i32::overflowing_div(a, b);
                 vm.stack.push(i_to_u(v));
                 overflow = o;
             }
             Operator::MODi => {
-                let a = u_to_i(vm.stack.pop().unwrap());
-                let b = u_to_i(vm.stack.pop().unwrap());
-                vm.stack.push(i_to_u(a % b));
+                let a = u_to_i(pop(vm, op)?);
+                let b = u_to_i(pop(vm, op)?);
+                if b == 0 {
+                    return Err(divide_by_zero(vm, op));
+                }
+                vm.stack.push(i_to_u(a.wrapping_rem(b)));
             }
             Operator::ADDfi => {
-                let a = u_to_f(vm.stack.pop().unwrap());
-                let b = u_to_i(vm.stack.pop().unwrap());
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_i(pop(vm, op)?);
                 vm.stack.push(f_to_u(a + b as f32));
             }
             Operator::SUBfi => {
-                let a = u_to_f(vm.stack.pop().unwrap());
-                let b = u_to_i(vm.stack.pop().unwrap());
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_i(pop(vm, op)?);
                 vm.stack.push(f_to_u(a - b as f32));
             }
             Operator::MULfi => {
-                let a = u_to_f(vm.stack.pop().unwrap());
-                let b = u_to_i(vm.stack.pop().unwrap());
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_i(pop(vm, op)?);
                 vm.stack.push(f_to_u(a * b as f32));
             }
             Operator::DIVfi => {
-                let a = u_to_f(vm.stack.pop().unwrap());
-                let b = u_to_i(vm.stack.pop().unwrap());
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_i(pop(vm, op)?);
                 vm.stack.push(f_to_u(a / b as f32));
             }
             Operator::MODfi => {
-                let a = u_to_f(vm.stack.pop().unwrap());
-                let b = u_to_i(vm.stack.pop().unwrap());
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_i(pop(vm, op)?);
                 vm.stack.push(f_to_u(a % b as f32));
             }
             Operator::ADDif => {
-                let a = u_to_i(vm.stack.pop().unwrap());
-                let b = u_to_f(vm.stack.pop().unwrap());
-                vm.stack.push(i_to_u(a + b as i32));
+                let a = u_to_i(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
+                let (v, o) = i32::overflowing_add(a, b as i32);
+                vm.stack.push(i_to_u(v));
+                overflow = o;
             }
             Operator::SUBif => {
-                let a = u_to_i(vm.stack.pop().unwrap());
-                let b = u_to_f(vm.stack.pop().unwrap());
-                vm.stack.push(i_to_u(a - b as i32));
+                let a = u_to_i(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
+                let (v, o) = i32::overflowing_sub(a, b as i32);
+                vm.stack.push(i_to_u(v));
+                overflow = o;
             }
             Operator::MULif => {
-                let a = u_to_i(vm.stack.pop().unwrap());
-                let b = u_to_f(vm.stack.pop().unwrap());
-                vm.stack.push(i_to_u(a * b as i32));
+                let a = u_to_i(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
+                let (v, o) = i32::overflowing_mul(a, b as i32);
+                vm.stack.push(i_to_u(v));
+                overflow = o;
             }
             Operator::DIVif => {
-                let a = u_to_i(vm.stack.pop().unwrap());
-                let b = u_to_f(vm.stack.pop().unwrap());
-                vm.stack.push(i_to_u(a / b as i32));
+                let a = u_to_i(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
+                if b as i32 == 0 {
+                    return Err(divide_by_zero(vm, op));
+                }
+                let (v, o) = i32::overflowing_div(a, b as i32);
+                vm.stack.push(i_to_u(v));
+                overflow = o;
             }
             Operator::MODif => {
-                let a = u_to_i(vm.stack.pop().unwrap());
-                let b = u_to_f(vm.stack.pop().unwrap());
-                vm.stack.push(i_to_u(a % b as i32));
+                let a = u_to_i(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
+                if b as i32 == 0 {
+                    return Err(divide_by_zero(vm, op));
+                }
+                vm.stack.push(i_to_u(a.wrapping_rem(b as i32)));
             }
             Operator::NEG => {
-                let a = vm.stack.pop().unwrap();
+                let a = pop(vm, op)?;
                 vm.stack.push(!a);
             }
             Operator::AND => {
-                let a = vm.stack.pop().unwrap();
-                let b = vm.stack.pop().unwrap();
+                let a = pop(vm, op)?;
+                let b = pop(vm, op)?;
                 vm.stack.push(a & b);
             }
             Operator::XOR => {
-                let a = vm.stack.pop().unwrap();
-                let b = vm.stack.pop().unwrap();
+                let a = pop(vm, op)?;
+                let b = pop(vm, op)?;
                 vm.stack.push(a ^ b);
             }
             Operator::NAND => {
-                let a = vm.stack.pop().unwrap();
-                let b = vm.stack.pop().unwrap();
+                let a = pop(vm, op)?;
+                let b = pop(vm, op)?;
                 vm.stack.push(!(a & b));
             }
+            Operator::RORC(amount)
+            | Operator::ROLC(amount)
+            | Operator::LSRC(amount)
+            | Operator::ASRC(amount)
+            | Operator::LSLC(amount)
+            | Operator::ASLC(amount) => {
+                let v = pop(vm, op)?;
+                vm.stack.push(shift(op, v, *amount));
+            }
+            Operator::RORD
+            | Operator::ROLD
+            | Operator::LSRD
+            | Operator::ASRD
+            | Operator::LSLD
+            | Operator::ASLD => {
+                let amount = pop(vm, op)?;
+                let v = pop(vm, op)?;
+                vm.stack.push(shift(op, v, amount));
+            }
+            Operator::CLZ => {
+                let v = pop(vm, op)?;
+                vm.stack.push(v.leading_zeros());
+            }
+            Operator::CTZ => {
+                let v = pop(vm, op)?;
+                vm.stack.push(v.trailing_zeros());
+            }
             Operator::CNT => {
-                let a = vm.stack.pop().unwrap();
+                let a = pop(vm, op)?;
                 let mut cnt = 0;
                 for i in 0..32 {
                     if a & (1 << i) != 0 {
@@ -280,292 +383,531 @@ pub mod op_calls {
                 vm.stack.push(cnt);
             }
             Operator::CMP => {
-                let a = vm.stack.pop().unwrap();
-                let b = vm.stack.pop().unwrap();
-                vm.stack.push(a - b);
+                let a = pop(vm, op)?;
+                let b = pop(vm, op)?;
+                vm.stack.push(a.wrapping_sub(b));
+            }
+            Operator::CMPu => {
+                let a = pop(vm, op)?;
+                let b = pop(vm, op)?;
+                vm.signal_compare = Some(a.cmp(&b));
+            }
+            Operator::CMPi => {
+                let a = pop(vm, op)? as i32;
+                let b = pop(vm, op)? as i32;
+                vm.signal_compare = Some(a.cmp(&b));
+            }
+            Operator::CMPf => {
+                let a = u_to_f(pop(vm, op)?);
+                let b = u_to_f(pop(vm, op)?);
+                vm.signal_compare = a.partial_cmp(&b);
+            }
+            Operator::SETREG64(register) => {
+                let register = register_index(vm, op, *register)?;
+                let value = pop_u64(vm, op)?;
+                vm.set_register64(register, value);
+            }
+            Operator::GETREG64(register) => {
+                let value = vm.register64(register_index(vm, op, *register)?);
+                vm.stack.push(value as u32);
+                vm.stack.push((value >> 32) as u32);
+            }
+            Operator::SETREG128(register) => {
+                let register = register_index(vm, op, *register)?;
+                let mut value: u128 = 0;
+                for word in (0..4).rev() {
+                    value |= (pop(vm, op)? as u128) << (word * 32);
+                }
+                vm.set_register128(register, value);
+            }
+            Operator::GETREG128(register) => {
+                let value = vm.register128(register_index(vm, op, *register)?);
+                for word in 0..4 {
+                    vm.stack.push((value >> (word * 32)) as u32);
+                }
+            }
+            Operator::ADD64u => overflow = arithmetic64(vm, u64::overflowing_add),
+            Operator::ADD64i => {
+                overflow = arithmetic64(vm, |a, b| {
+                    let (v, o) = (a as i64).overflowing_add(b as i64);
+                    (v as u64, o)
+                })
+            }
+            Operator::SUB64u => overflow = arithmetic64(vm, u64::overflowing_sub),
+            Operator::SUB64i => {
+                overflow = arithmetic64(vm, |a, b| {
+                    let (v, o) = (a as i64).overflowing_sub(b as i64);
+                    (v as u64, o)
+                })
+            }
+            Operator::MUL64u => overflow = arithmetic64(vm, u64::overflowing_mul),
+            Operator::MUL64i => {
+                overflow = arithmetic64(vm, |a, b| {
+                    let (v, o) = (a as i64).overflowing_mul(b as i64);
+                    (v as u64, o)
+                })
+            }
+            Operator::DIV64u => {
+                if vm.register64(1) == 0 {
+                    return Err(divide_by_zero(vm, op));
+                }
+                overflow = arithmetic64(vm, u64::overflowing_div);
+            }
+            Operator::DIV64i => {
+                if vm.register64(1) == 0 {
+                    return Err(divide_by_zero(vm, op));
+                }
+                overflow = arithmetic64(vm, |a, b| {
+                    let (v, o) = (a as i64).overflowing_div(b as i64);
+                    (v as u64, o)
+                });
+            }
+            Operator::LSL64 => {
+                let shift = pop(vm, op)?;
+                vm.set_register64(0, vm.register64(0).wrapping_shl(shift));
+            }
+            Operator::LSR64 => {
+                let shift = pop(vm, op)?;
+                vm.set_register64(0, vm.register64(0).wrapping_shr(shift));
+            }
+            Operator::ASR64 => {
+                let shift = pop(vm, op)?;
+                vm.set_register64(0, (vm.register64(0) as i64).wrapping_shr(shift) as u64);
+            }
+            Operator::CMP64u => {
+                vm.signal_compare = Some(vm.register64(0).cmp(&vm.register64(1)));
+            }
+            Operator::CMP64i => {
+                let (a, b) = (vm.register64(0) as i64, vm.register64(1) as i64);
+                vm.signal_compare = Some(a.cmp(&b));
+            }
+            Operator::ADD128u => overflow = arithmetic128(vm, u128::overflowing_add),
+            Operator::SUB128u => overflow = arithmetic128(vm, u128::overflowing_sub),
+            Operator::MUL128u => overflow = arithmetic128(vm, u128::overflowing_mul),
+            Operator::DIV128u => {
+                if vm.register128(1) == 0 {
+                    return Err(divide_by_zero(vm, op));
+                }
+                overflow = arithmetic128(vm, u128::overflowing_div);
+            }
+            Operator::LSL128 => {
+                let shift = pop(vm, op)?;
+                vm.set_register128(0, vm.register128(0).wrapping_shl(shift));
+            }
+            Operator::LSR128 => {
+                let shift = pop(vm, op)?;
+                vm.set_register128(0, vm.register128(0).wrapping_shr(shift));
+            }
+            Operator::CMP128u => {
+                vm.signal_compare = Some(vm.register128(0).cmp(&vm.register128(1)));
+            }
+            Operator::JMPlt(location)
+            | Operator::JMPgt(location)
+            | Operator::JMPle(location)
+            | Operator::JMPge(location) => {
+                if compare_holds(vm, op) {
+                    let ptr = jump_target(vm, op, location, targets[0])?;
+                    let before = vm.program_counter;
+                    vm.program_counter = ptr;
+
+                    has_changed_ptr = true;
+                    vm.stack_frame_pointers.push(StackFrame::new(before, vm.program_counter));
+                }
+            }
+            Operator::BRlt(location)
+            | Operator::BRgt(location)
+            | Operator::BRle(location)
+            | Operator::BRge(location) => {
+                if compare_holds(vm, op) {
+                    vm.program_counter = jump_target(vm, op, location, targets[0])?;
+                    has_changed_ptr = true;
+                }
             }
             Operator::JMPe(location) => {
-                let last_op = vm.stack.pop().unwrap();
+                let last_op = pop(vm, op)?;
                 if last_op == 0 {
                     // true is 0 because it is a compare by subtraction: if equal, result is 0
-                    let ptr = vm.jmp_table.get(&*location).unwrap();
+                    let ptr = jump_target(vm, op, location, targets[0])?;
                     let before = vm.program_counter;
-                    vm.program_counter = *ptr;
+                    vm.program_counter = ptr;
 
                     has_changed_ptr = true;
-                    vm.stack_frame_pointers.push((before, vm.program_counter));
+                    vm.stack_frame_pointers.push(StackFrame::new(before, vm.program_counter));
                 }
             }
             Operator::JMPne(location) => {
-                let last_op = vm.stack.pop().unwrap();
+                let last_op = pop(vm, op)?;
                 if last_op != 0 {
                     // false is non-0 because it is a compare by subtraction: if equal, result is 0, else false
-                    let ptr = vm.jmp_table.get(&*location).unwrap();
+                    let ptr = jump_target(vm, op, location, targets[0])?;
                     let before = vm.program_counter;
 
-                    vm.program_counter = *ptr;
+                    vm.program_counter = ptr;
 
                     has_changed_ptr = true;
-                    vm.stack_frame_pointers.push((before, vm.program_counter));
+                    vm.stack_frame_pointers.push(StackFrame::new(before, vm.program_counter));
                 }
             }
             Operator::SYSCALL(syscall_id, n_args) => {
                 let mut args = Vec::new();
-                for _ in 0..n_args {
-                    args.push(vm.stack.pop().unwrap());
+                for _ in 0..*n_args {
+                    args.push(pop(vm, op)?);
                 }
                 args.reverse();
-                let program_continue = VM::syscall(syscall_id, args);
+                let program_continue = vm.call_syscall(*syscall_id, args)?;
 
                 vm.signal_finished = !program_continue;
             }
             Operator::EXCEPT_THROW => {
-                //decrease program counter and inspect its operation until a CATCH is found, deallocating each allocation made in the meantime
-                //when it reaches the value of the most recent jump it will jump back to the previous value of the previous jump
-                let (mut before, mut after) = vm.stack_frame_pointers.pop().unwrap();
-
-                loop {
-                    vm.program_counter -= 1;
-                    let op = vm.program[vm.program_counter].borrow();
-
-                    //match op to find catch
-                    match op {
-                        Operator::EXCEPT_CATCH(catch_location) => {
-                            let before = vm.program_counter;
-                            let after = vm.jmp_table.get(catch_location).unwrap();
-                            vm.stack_frame_pointers.push((before, *after));
-                            vm.program_counter = *after;
-
-                            if vm.signal_debug {
-                                println!("CATCH FOUND: {},{}", before, catch_location);
-                            }
-                            //jump to catch
-                            break;
-                        }
-
-                        _ => {
-                            // not a catch, noop. in future will implement deallocations are required
-                        }
+                let exception = Exception::new(0, None, vm.program_counter);
+                vm.throw(exception, vm.program_counter)?;
+                has_changed_ptr = true;
+            }
+            Operator::EXCEPT_THROWC(code) => {
+                let exception = Exception::new(*code, None, vm.program_counter);
+                vm.throw(exception, vm.program_counter)?;
+                has_changed_ptr = true;
+            }
+            Operator::EXCEPT_THROWD => {
+                let code = pop(vm, op)?;
+                let exception = Exception::new(code, None, vm.program_counter);
+                vm.throw(exception, vm.program_counter)?;
+                has_changed_ptr = true;
+            }
+            Operator::EXCEPT_THROWP(code, ptr) => {
+                // taken away from the identifier so unwinding does not free it
+                allocation(vm, op, *ptr)?;
+                let payload = vm.detach_allocation(*ptr);
+                let exception = Exception::new(*code, payload, vm.program_counter);
+                vm.throw(exception, vm.program_counter)?;
+                has_changed_ptr = true;
+            }
+            Operator::EXCEPT_CATCH_RANGE(_, _, _) | Operator::EXCEPT_FINALLY(_) => {
+                //noop, handlers are found in vm.handlers during a throw
+            }
+            Operator::EXCEPT_END => {
+                let finally_at = vm.exception.as_ref().and_then(|exception| exception.finally_at);
+                if let Some(finally_at) = finally_at {
+                    // the finally block was entered by an exception, look for the next handler before it
+                    let mut exception = vm.exception.take().unwrap();
+                    exception.finally_at = None;
+                    vm.throw(exception, finally_at)?;
+                    has_changed_ptr = true;
+                } else {
+                    // the exception has been handled, the handlers in this frame apply again
+                    if let Some(Exception { payload: Some((location, size)), .. }) = vm.exception.take() {
+                        vm.allocator.free(location, size as usize);
                     }
-
-                    if vm.program_counter == after {
-                        vm.program_counter = before;
-                        let temp = vm.stack_frame_pointers.pop().unwrap();
-                        before = temp.0;
-                        after = temp.1;
+                    if let Some(frame) = vm.stack_frame_pointers.last_mut() {
+                        frame.handling = None;
                     }
                 }
             }
+            Operator::EXCEPT_CODE => {
+                let code = handled_exception(vm, op)?.code;
+                vm.stack.push(code);
+            }
+            Operator::EXCEPT_PAYLOAD(ptr) => {
+                let payload = handled_exception(vm, op)?.payload.take();
+                let (location, size) = match payload {
+                    Some(payload) => payload,
+                    None => {
+                        return Err(VmError::InvalidOperation {
+                            pc: vm.program_counter,
+                            op: Box::new(op.clone()),
+                            reason: "the exception has no payload".to_string(),
+                        })
+                    }
+                };
+                vm.deallocate(*ptr);
+                vm.static_alloc_table.insert(*ptr, (location, size));
+                vm.own_allocation(*ptr);
+            }
             Operator::EXCEPT_CATCH(_handler) => {
-                //noop, catches are handled during a throw unwrap
+                //noop, handlers are found in vm.handlers during a throw
             }
             Operator::LABEL(str) => {
                 if vm.signal_debug {
-                    println!("found label {} at position {}", str, vm.program_counter);
+                    let pc = vm.program_counter;
+                    vm.write_trace(format_args!("found label {} at position {}", str, pc))?;
                 }
             }
 
             Operator::DEALLOC(ptr) => {
-                let v = vm.static_alloc_table[&ptr];
-                let (stack_location, size) = v;
-                //zero out the memory at the location
-                for i in stack_location..(stack_location + (size as usize)) {
-                    vm.memory[i] = 0;
-                }
-
-                vm.static_alloc_table.remove(&ptr);
+                //memory is zeroed when the allocator hands it out again
+                allocation(vm, op, *ptr)?;
+                vm.deallocate(*ptr);
             }
             Operator::JMP_DEF(_, _) => {
-                panic!("JMP_DEF found after other instructions");
                 //do nothing here, these functions are runtime but the jump definitions are handled externally
                 // and should never be handed to this function
                 //jump defs require extra handling
+                return Err(VmError::InvalidOperation {
+                    pc: vm.program_counter,
+                    op: Box::new(op.clone()),
+                    reason: "JMP_DEF found after other instructions".to_string(),
+                });
             }
             Operator::JMP(location) => {
-                let ptr = vm.jmp_table.get(&*location).unwrap();
+                let ptr = jump_target(vm, op, location, targets[0])?;
                 let before = vm.program_counter;
 
-                vm.program_counter = *ptr;
+                vm.program_counter = ptr;
 
                 has_changed_ptr = true;
-                vm.stack_frame_pointers.push((before, vm.program_counter));
+                vm.stack_frame_pointers.push(StackFrame::new(before, vm.program_counter));
             }
             Operator::RET => {
-                vm.stack_frame_pointers.pop();
-                if vm.stack_frame_pointers.len() == 0 {
-                    vm.signal_finished = true;
-                    vm.program_counter = 0;
-                    return true;
+                // closing the outermost frame (main, or the function a library was called with) ends the program
+                match vm.pop_frame() {
+                    Some(frame) if !vm.stack_frame_pointers.is_empty() => {
+                        //go back to the call, the program counter then moves on to the operation after it
+                        vm.program_counter = frame.from;
+                    }
+                    _ => {
+                        vm.signal_finished = true;
+                        vm.program_counter = 0;
+                        return Ok(true);
+                    }
                 }
-
+            }
+            Operator::CALL(location) => {
+                let ptr = jump_target(vm, op, location, targets[0])?;
+                call(vm, ptr);
                 has_changed_ptr = true;
-
-                let (before, _) = vm.stack_frame_pointers.last().unwrap();
-                //go to before
-                vm.program_counter = *before
+            }
+            Operator::CALLD => {
+                let ptr = pop_address(vm, op)?;
+                call(vm, ptr);
+                has_changed_ptr = true;
+            }
+            Operator::BR(location) => {
+                vm.program_counter = jump_target(vm, op, location, targets[0])?;
+                has_changed_ptr = true;
+            }
+            Operator::BRo(location) => {
+                if vm.signal_overflow {
+                    vm.program_counter = jump_target(vm, op, location, targets[0])?;
+                    has_changed_ptr = true;
+                }
+            }
+            Operator::BRe(location) => {
+                if pop(vm, op)? == 0 {
+                    vm.program_counter = jump_target(vm, op, location, targets[0])?;
+                    has_changed_ptr = true;
+                }
+            }
+            Operator::BRne(location) => {
+                if pop(vm, op)? != 0 {
+                    vm.program_counter = jump_target(vm, op, location, targets[0])?;
+                    has_changed_ptr = true;
+                }
+            }
+            Operator::BRs(_true, _false) => {
+                vm.program_counter = if pop(vm, op)? == 0 {
+                    jump_target(vm, op, _true, targets[0])?
+                } else {
+                    jump_target(vm, op, _false, targets[1])?
+                };
+                has_changed_ptr = true;
+            }
+            Operator::DBR => {
+                vm.program_counter = pop_address(vm, op)?;
+                has_changed_ptr = true;
+            }
+            Operator::DBRe => {
+                let l = pop(vm, op)?;
+                let r = pop(vm, op)?;
+                let ptr = pop_address(vm, op)?;
+                if l == r {
+                    vm.program_counter = ptr;
+                    has_changed_ptr = true;
+                }
+            }
+            Operator::DBRne => {
+                let l = pop(vm, op)?;
+                let r = pop(vm, op)?;
+                let ptr = pop_address(vm, op)?;
+                if l != r {
+                    vm.program_counter = ptr;
+                    has_changed_ptr = true;
+                }
             }
             Operator::JMPs(_true, _false) => {
-                let last_op = vm.stack.pop().unwrap();
+                let last_op = pop(vm, op)?;
 
                 let ptr = if last_op == 0 {
-                    vm.jmp_table.get(&*_true).unwrap()
+                    jump_target(vm, op, _true, targets[0])?
                 } else {
-                    vm.jmp_table.get(&*_false).unwrap()
+                    jump_target(vm, op, _false, targets[1])?
                 };
                 let before = vm.program_counter;
-                vm.program_counter = *ptr;
+                vm.program_counter = ptr;
                 has_changed_ptr = true;
-                vm.stack_frame_pointers.push((before, vm.program_counter));
+                vm.stack_frame_pointers.push(StackFrame::new(before, vm.program_counter));
             }
             Operator::GETBYTELEN(ptr) => {
-                let v = vm.static_alloc_table.get(&ptr).unwrap();
-                let (stack_location, size) = v;
-                let mut unbuffered_count = (*size) * 4;
-                let ptr = (*stack_location) + (*size as usize);
-                let word = vm.memory[ptr];
-                let bytes = u_to_bytes(word);
-                for byte in bytes {
-                    if byte == 0 {
-                        break;
+                // every word but the last is full, the last word is counted up to its first null byte
+                let (stack_location, size) = allocation(vm, op, *ptr)?;
+                let mut unbuffered_count = 0;
+                if size > 0 {
+                    unbuffered_count = (size - 1) * 4;
+                    let word = read_memory(vm, op, stack_location + (size as usize) - 1)?;
+                    let bytes = u_to_bytes(word);
+                    for byte in bytes {
+                        if byte == 0 {
+                            break;
+                        }
+                        unbuffered_count += 1;
                     }
-                    unbuffered_count += 1;
                 }
 
                 vm.stack.push(unbuffered_count);
             }
             Operator::GETBYTE(ptr, offset) => {
-                let v = vm.static_alloc_table.get(&ptr).unwrap();
-                let (stack_location, size) = v;
+                let (stack_location, size) = allocation(vm, op, *ptr)?;
                 let mut buffer: Vec<u8> = vec![];
 
-                for i in 0..*size {
-                    let word = vm.memory[(*stack_location) + i as usize];
+                for i in 0..size {
+                    let word = read_memory(vm, op, stack_location + i as usize)?;
                     let bytes = u_to_bytes(word);
                     buffer.extend(bytes);
                 }
 
-                if offset >= buffer.len() {
-                    panic!("offset out of bounds");
-                }
-                let v = buffer.get(offset as usize).unwrap();
+                let v = match buffer.get(*offset) {
+                    Some(v) => *v,
+                    None => return Err(out_of_bounds(vm, op, *offset, buffer.len())),
+                };
 
-                vm.stack.push(*v as u32);
+                vm.stack.push(v as u32);
             }
             Operator::GETWORD(ptr, offset) => {
-                let v = vm.static_alloc_table.get(&ptr).unwrap();
-                let (stack_location, _size) = v;
-                let loc = (*stack_location) + (offset);
-                let word = vm.memory[loc];
+                let (stack_location, size) = allocation(vm, op, *ptr)?;
+                if *offset >= size as usize {
+                    return Err(out_of_bounds(vm, op, *offset, size as usize));
+                }
+                let loc = stack_location + offset;
+                let word = read_memory(vm, op, loc)?;
                 vm.stack.push(word);
             }
             Operator::SETBYTE(ptr, offset, value) => {
-                let v = vm.static_alloc_table.get(&ptr).unwrap();
-                let (stack_location, _size) = v;
-                let chunk = (offset) / 4;
-                let offset = (offset) % 4;
-                let loc = (stack_location) + (chunk);
-                let word = vm.memory[loc];
+                let (stack_location, size) = allocation(vm, op, *ptr)?;
+                if *offset >= (size as usize) * 4 {
+                    return Err(out_of_bounds(vm, op, *offset, (size as usize) * 4));
+                }
+                let chunk = offset / 4;
+                let offset = offset % 4;
+                let loc = stack_location + chunk;
+                let word = read_memory(vm, op, loc)?;
                 let mut bytes = u_to_bytes(word);
-                bytes[offset] = value as u8;
+                bytes[offset] = *value;
                 let new_word = bytes_to_u(bytes);
-                vm.memory[loc] = new_word;
+                write_memory(vm, op, loc, new_word)?;
             }
             Operator::SETWORD(ptr, offset, value) => {
-                let v = vm.static_alloc_table.get(&ptr).unwrap();
-                let (stack_location, _size) = v;
-                let loc = (stack_location) + (offset);
-                vm.memory[loc] = value;
+                let (stack_location, size) = allocation(vm, op, *ptr)?;
+                if *offset >= size as usize {
+                    return Err(out_of_bounds(vm, op, *offset, size as usize));
+                }
+                let loc = stack_location + offset;
+                write_memory(vm, op, loc, *value)?;
             }
             Operator::JMP_SCAN => {
                 //noop, this is run during prepare()
             }
             Operator::ROR => {
-                let v = vm.stack.pop().unwrap();
+                let v = pop(vm, op)?;
                 let v = v.rotate_right(1);
                 vm.stack.push(v);
             }
             Operator::ROL => {
-                let v = vm.stack.pop().unwrap();
+                let v = pop(vm, op)?;
                 let v = v.rotate_left(1);
                 vm.stack.push(v);
             }
             Operator::LSR => {
-                let v = vm.stack.pop().unwrap();
+                let v = pop(vm, op)?;
                 let v = v >> 1;
                 vm.stack.push(v);
             }
             Operator::ASR => {
-                let v = vm.stack.pop().unwrap();
+                let v = pop(vm, op)?;
                 let v = ((v as i32) >> 1) as u32;
                 vm.stack.push(v);
             }
             Operator::LSL => {
-                let v = vm.stack.pop().unwrap();
+                let v = pop(vm, op)?;
                 let v = v << 1;
                 vm.stack.push(v);
             }
             Operator::ASL => {
-                let v = vm.stack.pop().unwrap();
+                let v = pop(vm, op)?;
                 let v = ((v as i32) << 1) as u32;
                 vm.stack.push(v);
             }
             Operator::ADDu => {
-                let v1 = vm.stack.pop().unwrap();
-                let v2 = vm.stack.pop().unwrap();
+                let v1 = pop(vm, op)?;
+                let v2 = pop(vm, op)?;
                 let (v, o) = u32::overflowing_add(v1, v2);
                 vm.stack.push(v);
                 overflow = o;
             }
             Operator::SUBu => {
-                let v1 = vm.stack.pop().unwrap();
-                let v2 = vm.stack.pop().unwrap();
+                let v1 = pop(vm, op)?;
+                let v2 = pop(vm, op)?;
                 let (v, o) = u32::overflowing_sub(v1, v2);
                 vm.stack.push(v);
                 overflow = o;
             }
             Operator::MULu => {
-                let v1 = vm.stack.pop().unwrap();
-                let v2 = vm.stack.pop().unwrap();
+                let v1 = pop(vm, op)?;
+                let v2 = pop(vm, op)?;
                 let (v, o) = u32::overflowing_mul(v1, v2);
                 vm.stack.push(v);
                 overflow = o;
             }
             Operator::DIVu => {
-                let v1 = vm.stack.pop().unwrap();
-                let v2 = vm.stack.pop().unwrap();
+                let v1 = pop(vm, op)?;
+                let v2 = pop(vm, op)?;
+                if v2 == 0 {
+                    return Err(divide_by_zero(vm, op));
+                }
                 let (v, o) = u32::overflowing_div(v1, v2);
                 vm.stack.push(v);
                 overflow = o;
             }
             Operator::MODu => {
-                let v1 = vm.stack.pop().unwrap();
-                let v2 = vm.stack.pop().unwrap();
+                let v1 = pop(vm, op)?;
+                let v2 = pop(vm, op)?;
+                if v2 == 0 {
+                    return Err(divide_by_zero(vm, op));
+                }
                 vm.stack.push(v1 % v2);
             }
             Operator::JMPo(location) => {
                 if vm.signal_overflow {
-                    let ptr = vm.jmp_table.get(&*location).unwrap();
+                    let ptr = jump_target(vm, op, location, targets[0])?;
                     let before = vm.program_counter;
 
-                    vm.program_counter = *ptr;
+                    vm.program_counter = ptr;
 
                     has_changed_ptr = true;
-                    vm.stack_frame_pointers.push((before, vm.program_counter));
+                    vm.stack_frame_pointers.push(StackFrame::new(before, vm.program_counter));
                 }
             }
             Operator::SYSCALLD(syscall_id) => {
-                let n_args = vm.stack.pop().unwrap();
+                let n_args = pop(vm, op)?;
                 let mut args = Vec::new();
                 for _ in 0..n_args {
-                    args.push(vm.stack.pop().unwrap());
+                    args.push(pop(vm, op)?);
                 }
                 args.reverse();
-                let program_continue = VM::syscall(syscall_id, args);
+                let program_continue = vm.call_syscall(*syscall_id, args)?;
 
                 vm.signal_finished = !program_continue;
             }
             Operator::EMIT => {
-                let v = vm.stack.pop().unwrap();
+                let v = pop(vm, op)?;
                 vm.output.push(v);
             }
             Operator::EMITS(_v) => {
@@ -573,50 +915,114 @@ pub mod op_calls {
                 //will do in the future
             }
             Operator::EMITW(ptr) => {
-                let v = vm.static_alloc_table[&ptr];
-                let (stack_location, _) = v;
-                let val = vm.memory[stack_location];
+                let (stack_location, _) = allocation(vm, op, *ptr)?;
+                let val = read_memory(vm, op, stack_location)?;
                 vm.output.push(val);
             }
             Operator::EMITD(ptr) => {
-                let v = vm.static_alloc_table[&ptr];
-                let (stack_location, size) = v;
+                let (stack_location, size) = allocation(vm, op, *ptr)?;
                 let size = size as usize;
                 for i in 0..size {
-                    let val = vm.memory[stack_location + i];
+                    let val = read_memory(vm, op, stack_location + i)?;
                     vm.output.push(val);
                 }
 
                 vm.output.push(size as u32);
             }
             Operator::DUP => {
-                let v = vm.stack.last().unwrap().clone();
+                let v = match vm.stack.last() {
+                    Some(v) => *v,
+                    None => return Err(stack_underflow(vm, op)),
+                };
                 vm.stack.push(v);
             }
             Operator::DUPO(offset) => {
-                let v = vm.stack[vm.stack.len() - offset].clone();
+                let index = match vm.stack.len().checked_sub(*offset) {
+                    Some(index) => index,
+                    None => return Err(stack_underflow(vm, op)),
+                };
+                let v = match vm.stack.get(index) {
+                    Some(v) => *v,
+                    None => return Err(out_of_bounds(vm, op, index, vm.stack.len())),
+                };
+                vm.stack.push(v);
+            }
+            Operator::OVER => {
+                let v = vm.stack[depth(vm, op, 1)?];
+                vm.stack.push(v);
+            }
+            Operator::ROT => {
+                let index = depth(vm, op, 2)?;
+                let v = vm.stack.remove(index);
                 vm.stack.push(v);
             }
+            Operator::PICK(n) => {
+                let v = vm.stack[depth(vm, op, *n)?];
+                vm.stack.push(v);
+            }
+            Operator::ROLL(n) => {
+                let index = depth(vm, op, *n)?;
+                let v = vm.stack.remove(index);
+                vm.stack.push(v);
+            }
+            Operator::DROP(n) => {
+                if *n > 0 {
+                    let index = depth(vm, op, *n - 1)?;
+                    vm.stack.truncate(index);
+                }
+            }
+            Operator::DEPTH => {
+                vm.stack.push(vm.stack.len() as u32);
+            }
+            Operator::CLEAR => {
+                vm.stack.clear();
+            }
+            Operator::ENTER(n) => {
+                // the frame's current slots are replaced, the locals of every other frame and memory stay
+                let held = frame(vm, op)?.locals.len();
+                let limit = vm.limits.max_memory_words;
+                let in_use = vm.memory.len() + vm.local_words - held;
+                if *n > limit.saturating_sub(in_use) {
+                    return Err(VmError::MemoryLimit {
+                        pc: vm.program_counter,
+                        op: Box::new(op.clone()),
+                        requested: *n,
+                        limit,
+                    });
+                }
+                frame(vm, op)?.locals.resize(*n, 0);
+                vm.local_words = vm.local_words - held + *n;
+            }
+            Operator::LOCAL_GET(index) => {
+                let v = *local(vm, op, *index)?;
+                vm.stack.push(v);
+            }
+            Operator::LOCAL_SET(index) => {
+                // checked before popping so a bad index leaves the stack alone
+                local(vm, op, *index)?;
+                let v = pop(vm, op)?;
+                *local(vm, op, *index)? = v;
+            }
             Operator::SWAP => {
-                let v1 = vm.stack.pop().unwrap();
-                let v2 = vm.stack.pop().unwrap();
+                let v1 = pop(vm, op)?;
+                let v2 = pop(vm, op)?;
                 vm.stack.push(v1);
                 vm.stack.push(v2);
             }
             Operator::OR => {
-                let v1 = vm.stack.pop().unwrap();
-                let v2 = vm.stack.pop().unwrap();
+                let v1 = pop(vm, op)?;
+                let v2 = pop(vm, op)?;
                 vm.stack.push(v1 | v2);
             }
             Operator::NOR => {
-                let v1 = vm.stack.pop().unwrap();
-                let v2 = vm.stack.pop().unwrap();
+                let v1 = pop(vm, op)?;
+                let v2 = pop(vm, op)?;
                 vm.stack.push(!(v1 | v2));
             }
             Operator::DJMP => {
                 //pop 2, jump
-                let v1 = vm.stack.pop().unwrap();
-                let v2 = vm.stack.pop().unwrap();
+                let v1 = pop(vm, op)?;
+                let v2 = pop(vm, op)?;
                 let _leftbytes = v1.to_be_bytes();
                 let _rightbytes = v2.to_be_bytes();
                 let _bytes = [
@@ -629,19 +1035,19 @@ pub mod op_calls {
                     _rightbytes[2],
                     _rightbytes[3],
                 ];
-                let ptr = usize::from_be_bytes(_bytes);
+                let ptr = u64::from_be_bytes(_bytes) as usize;
                 let before = vm.program_counter;
                 vm.program_counter = ptr;
 
                 has_changed_ptr = true;
-                vm.stack_frame_pointers.push((before, vm.program_counter));
+                vm.stack_frame_pointers.push(StackFrame::new(before, vm.program_counter));
             }
             Operator::DJMPe => {
                 //pop 2, compare, pop 2, jump if first 2 were equal
-                let l = vm.stack.pop().unwrap();
-                let r = vm.stack.pop().unwrap();
-                let v1 = vm.stack.pop().unwrap();
-                let v2 = vm.stack.pop().unwrap();
+                let l = pop(vm, op)?;
+                let r = pop(vm, op)?;
+                let v1 = pop(vm, op)?;
+                let v2 = pop(vm, op)?;
                 let _leftbytes = v1.to_be_bytes();
                 let _rightbytes = v2.to_be_bytes();
                 let _bytes = [
@@ -654,21 +1060,21 @@ pub mod op_calls {
                     _rightbytes[2],
                     _rightbytes[3],
                 ];
-                let ptr = usize::from_be_bytes(_bytes);
+                let ptr = u64::from_be_bytes(_bytes) as usize;
 
-                if (l - r) == 0 {
+                if l == r {
                     let before = vm.program_counter;
                     vm.program_counter = ptr;
 
                     has_changed_ptr = true;
-                    vm.stack_frame_pointers.push((before, vm.program_counter));
+                    vm.stack_frame_pointers.push(StackFrame::new(before, vm.program_counter));
                 }
             }
             Operator::DJMPne => {
-                let l = vm.stack.pop().unwrap();
-                let r = vm.stack.pop().unwrap();
-                let v1 = vm.stack.pop().unwrap();
-                let v2 = vm.stack.pop().unwrap();
+                let l = pop(vm, op)?;
+                let r = pop(vm, op)?;
+                let v1 = pop(vm, op)?;
+                let v2 = pop(vm, op)?;
                 let _leftbytes = v1.to_be_bytes();
                 let _rightbytes = v2.to_be_bytes();
                 let _bytes = [
@@ -681,95 +1087,335 @@ pub mod op_calls {
                     _rightbytes[2],
                     _rightbytes[3],
                 ];
-                let ptr = usize::from_be_bytes(_bytes);
-                let cmp = l - r;
-                if cmp != 0 {
+                let ptr = u64::from_be_bytes(_bytes) as usize;
+                if l != r {
                     let before = vm.program_counter;
                     vm.program_counter = ptr;
 
                     has_changed_ptr = true;
-                    vm.stack_frame_pointers.push((before, vm.program_counter));
+                    vm.stack_frame_pointers.push(StackFrame::new(before, vm.program_counter));
                 };
             }
             Operator::DALLOC(identifier) => {
-                let size = vm.stack.pop().unwrap();
-                let mut allocated_memory_location = 0;
-                {
-                    let mut _s = false;
-                    let table = vm.static_alloc_table.borrow_mut();
-
-                    //get all keys as a vector
-                    let keys: Vec<usize> = table.keys().map(|x| *x).collect();
-                    let l = keys.len();
-                    for x in 0..l {
-                        // if the current key is not the final one in the allocations, check if the required size fits between current+len and next
-                        if x < l {
-                            let current_pointer = keys.get(x).unwrap();
-                            let next_pointer = keys.get(x + 1).unwrap();
-                            let v = vm.static_alloc_table[&current_pointer];
-                            let (stack_location, s) = v;
-                            let (next_stack_location, _) = vm.static_alloc_table[&next_pointer];
-                            if stack_location + s as usize + (size as usize) < next_stack_location {
-                                let allocation = (stack_location, s + size);
-                                let p = identifier;
-                                vm.static_alloc_table.insert(p, allocation);
-                                allocated_memory_location = p;
-                                _s = true;
-                                break;
-                            }
-                        }
-                    }
-
-                    if !_s {
-                        let end_of_stack = vm.memory.len();
-                        let allocation = (end_of_stack, size);
-                        vm.static_alloc_table.insert(identifier, allocation);
-                        for _ in 0..size {
-                            vm.memory.push(0);
-                        }
-
-                        allocated_memory_location = end_of_stack
-                    }
+                //the allocator zeroes the memory it hands out
+                let size = pop(vm, op)?;
+                vm.allocate(*identifier, size)?;
+            }
+            Operator::DNEW => {
+                let size = pop(vm, op)?;
+                let location = vm.dyn_allocate(size)?;
+                push_pointer(vm, location);
+            }
+            Operator::DGETSIZE => {
+                let (_, size) = pop_pointer(vm, op)?;
+                vm.stack.push(size);
+            }
+            Operator::DLOADVALUE => {
+                let (location, size) = pop_pointer(vm, op)?;
+                for i in 0..size as usize {
+                    let val = read_memory(vm, op, location + i)?;
+                    vm.stack.push(val);
                 }
 
-                for i in 0..size {
-                    vm.memory[allocated_memory_location + i as usize] = 0;
+                vm.stack.push(size);
+            }
+            Operator::DDEALLOC => {
+                let (location, _) = pop_pointer(vm, op)?;
+                vm.dyn_deallocate(location);
+            }
+            Operator::DGETWORD => {
+                let (location, size) = pop_pointer(vm, op)?;
+                let index = pop(vm, op)? as usize;
+                if index >= size as usize {
+                    return Err(out_of_bounds(vm, op, index, size as usize));
                 }
+                let word = read_memory(vm, op, location + index)?;
+                vm.stack.push(word);
+            }
+            Operator::DSETWORD => {
+                let (location, size) = pop_pointer(vm, op)?;
+                let index = pop(vm, op)? as usize;
+                let value = pop(vm, op)?;
+                if index >= size as usize {
+                    return Err(out_of_bounds(vm, op, index, size as usize));
+                }
+                write_memory(vm, op, location + index, value)?;
+            }
+            Operator::DGETBYTE => {
+                let (location, size) = pop_pointer(vm, op)?;
+                let index = pop(vm, op)? as usize;
+                if index >= (size as usize) * 4 {
+                    return Err(out_of_bounds(vm, op, index, (size as usize) * 4));
+                }
+                let word = read_memory(vm, op, location + index / 4)?;
+                let bytes = u_to_bytes(word);
+                vm.stack.push(bytes[index % 4] as u32);
+            }
+            Operator::DSETBYTE => {
+                let (location, size) = pop_pointer(vm, op)?;
+                let index = pop(vm, op)? as usize;
+                let value = pop(vm, op)?;
+                if index >= (size as usize) * 4 {
+                    return Err(out_of_bounds(vm, op, index, (size as usize) * 4));
+                }
+                let loc = location + index / 4;
+                let word = read_memory(vm, op, loc)?;
+                let mut bytes = u_to_bytes(word);
+                bytes[index % 4] = value as u8;
+                write_memory(vm, op, loc, bytes_to_u(bytes))?;
             }
             Operator::LIBLOAD(library) => {
                 if !loaded_libs.contains_key(&*library.clone()) {
-                    let lib = stal_dll::load_library(&*library);
+                    let lib = load_library(vm, op, library)?;
                     loaded_libs.insert(library.clone(), lib);
                 }
             }
             Operator::DLIBLOAD => {
-                let library = vm.get_next_string();
+                let library = vm.get_next_string()?;
                 if !loaded_libs.contains_key(&*library.clone()) {
-                    let lib = stal_dll::load_library(&*library);
+                    let lib = load_library(vm, op, &library)?;
                     loaded_libs.insert(library.clone(), lib);
                 }
             }
             Operator::LIBCALL(library, label) => {
-                vm.call_dynamic_library(loaded_libs, library, label)
+                vm.call_dynamic_library(loaded_libs, library.clone(), label.clone())?
             }
             Operator::DLIBCALL(label) => {
-                let library = vm.get_next_string();
-                vm.call_dynamic_library(loaded_libs, library, label)
+                let library = vm.get_next_string()?;
+                vm.call_dynamic_library(loaded_libs, library, label.clone())?
             }
             Operator::LIBDCALL(library) => {
-                let label = vm.get_next_string();
-                vm.call_dynamic_library(loaded_libs, library, label)
+                let label = vm.get_next_string()?;
+                vm.call_dynamic_library(loaded_libs, library.clone(), label)?
             }
             Operator::DLIBDCALL => {
-                let library = vm.get_next_string();
-                let label = vm.get_next_string();
-                vm.call_dynamic_library(loaded_libs, library, label)
+                let library = vm.get_next_string()?;
+                let label = vm.get_next_string()?;
+                vm.call_dynamic_library(loaded_libs, library, label)?
             }
         }
 
         vm.signal_overflow = overflow;
 
-        has_changed_ptr
+        Ok(has_changed_ptr)
+    }
+
+    fn load_library(vm: &VM, op: &Operator, library: &str) -> Result<StalDynamicLibrary, VmError> {
+        match stal_dll::load_library(library) {
+            Ok(lib) => Ok(lib),
+            Err(error) => Err(VmError::LibraryLoad {
+                pc: vm.program_counter,
+                op: Box::new(op.clone()),
+                library: library.to_string(),
+                error,
+            }),
+        }
+    }
+
+    fn pop(vm: &mut VM, op: &Operator) -> Result<u32, VmError> {
+        match vm.stack.pop() {
+            Some(v) => Ok(v),
+            None => Err(stack_underflow(vm, op)),
+        }
+    }
+
+    // the program counter a label operand was lowered to
+    fn jump_target(vm: &VM, op: &Operator, label: &str, target: Option<usize>) -> Result<usize, VmError> {
+        match target {
+            Some(ptr) => Ok(ptr),
+            None => Err(VmError::UnknownLabel {
+                pc: vm.program_counter,
+                op: Box::new(op.clone()),
+                label: label.to_string(),
+            }),
+        }
+    }
+
+    // the exception a catch or finally block is handling
+    fn handled_exception<'a>(vm: &'a mut VM, op: &Operator) -> Result<&'a mut Exception, VmError> {
+        let pc = vm.program_counter;
+        match vm.exception.as_mut() {
+            Some(exception) => Ok(exception),
+            None => Err(VmError::InvalidOperation {
+                pc,
+                op: Box::new(op.clone()),
+                reason: "no exception is being handled".to_string(),
+            }),
+        }
+    }
+
+    // opens a stack frame for a call from the current program counter, RET comes back to it
+    fn call(vm: &mut VM, ptr: usize) {
+        let before = vm.program_counter;
+        vm.program_counter = ptr;
+        vm.stack_frame_pointers.push(StackFrame::new(before, ptr));
+    }
+
+    // shifts or rotates v by amount for the C and D shift operators, only the low 5 bits of amount are used
+    fn shift(op: &Operator, v: u32, amount: u32) -> u32 {
+        match op {
+            Operator::RORC(_) | Operator::RORD => v.rotate_right(amount),
+            Operator::ROLC(_) | Operator::ROLD => v.rotate_left(amount),
+            Operator::LSRC(_) | Operator::LSRD => v.wrapping_shr(amount),
+            Operator::ASRC(_) | Operator::ASRD => (v as i32).wrapping_shr(amount) as u32,
+            _ => v.wrapping_shl(amount),
+        }
+    }
+
+    // whether the conditional JMP or BR op is taken on vm.signal_compare
+    fn compare_holds(vm: &VM, op: &Operator) -> bool {
+        match (op, vm.signal_compare) {
+            (_, None) => false,
+            (Operator::JMPlt(_) | Operator::BRlt(_), Some(ordering)) => ordering == Ordering::Less,
+            (Operator::JMPgt(_) | Operator::BRgt(_), Some(ordering)) => ordering == Ordering::Greater,
+            (Operator::JMPle(_) | Operator::BRle(_), Some(ordering)) => ordering != Ordering::Greater,
+            (Operator::JMPge(_) | Operator::BRge(_), Some(ordering)) => ordering != Ordering::Less,
+            _ => false,
+        }
+    }
+
+    // checks the register operand of SETREG64 / GETREG64 / SETREG128 / GETREG128, there are 2 of each width
+    fn register_index(vm: &VM, op: &Operator, register: usize) -> Result<usize, VmError> {
+        match register {
+            0 | 1 => Ok(register),
+            _ => Err(out_of_bounds(vm, op, register, 2)),
+        }
+    }
+
+    // register 0 = register 0 <f> register 1, returns whether it overflowed
+    fn arithmetic64(vm: &mut VM, f: impl Fn(u64, u64) -> (u64, bool)) -> bool {
+        let (v, o) = f(vm.register64(0), vm.register64(1));
+        vm.set_register64(0, v);
+        o
+    }
+
+    fn arithmetic128(vm: &mut VM, f: impl Fn(u128, u128) -> (u128, bool)) -> bool {
+        let (v, o) = f(vm.register128(0), vm.register128(1));
+        vm.set_register128(0, v);
+        o
+    }
+
+    // pops a 2 word value, high word on top
+    fn pop_u64(vm: &mut VM, op: &Operator) -> Result<u64, VmError> {
+        let high = pop(vm, op)? as u64;
+        let low = pop(vm, op)? as u64;
+        Ok((high << 32) | low)
+    }
+
+    // pops a 2 word program counter, high word on top (as DJMP)
+    fn pop_address(vm: &mut VM, op: &Operator) -> Result<usize, VmError> {
+        Ok(pop_u64(vm, op)? as usize)
+    }
+
+    // (location, size) of an entry in the static_alloc_table
+    fn allocation(vm: &VM, op: &Operator, ptr: usize) -> Result<(usize, u32), VmError> {
+        match vm.static_alloc_table.get(&ptr) {
+            Some(v) => Ok(*v),
+            None => Err(VmError::UnknownAllocation {
+                pc: vm.program_counter,
+                op: Box::new(op.clone()),
+                ptr,
+            }),
+        }
+    }
+
+    // pushes a heap pointer, low word first so the high word is on top
+    fn push_pointer(vm: &mut VM, location: usize) {
+        let pointer = location as u64;
+        vm.stack.push(pointer as u32);
+        vm.stack.push((pointer >> 32) as u32);
+    }
+
+    // pops a heap pointer and returns the (location, size) of the allocation it points at
+    fn pop_pointer(vm: &mut VM, op: &Operator) -> Result<(usize, u32), VmError> {
+        let pointer = pop_u64(vm, op)?;
+
+        let allocation = usize::try_from(pointer)
+            .ok()
+            .and_then(|location| vm.dynamic_allocations.get(&location).map(|size| (location, *size)));
+        match allocation {
+            Some(allocation) => Ok(allocation),
+            None => Err(VmError::InvalidPointer {
+                pc: vm.program_counter,
+                op: Box::new(op.clone()),
+                pointer,
+            }),
+        }
+    }
+
+    fn read_memory(vm: &VM, op: &Operator, index: usize) -> Result<u32, VmError> {
+        match vm.memory.get(index) {
+            Some(v) => Ok(*v),
+            None => Err(out_of_bounds(vm, op, index, vm.memory.len())),
+        }
+    }
+
+    fn write_memory(vm: &mut VM, op: &Operator, index: usize, value: u32) -> Result<(), VmError> {
+        if index >= vm.memory.len() {
+            return Err(out_of_bounds(vm, op, index, vm.memory.len()));
+        }
+        vm.memory[index] = value;
+        Ok(())
+    }
+
+    // the innermost stack frame, which a VM only has once prepare has opened main's
+    fn frame<'a>(vm: &'a mut VM, op: &Operator) -> Result<&'a mut StackFrame, VmError> {
+        let pc = vm.program_counter;
+        match vm.stack_frame_pointers.last_mut() {
+            Some(frame) => Ok(frame),
+            None => Err(VmError::InvalidOperation {
+                pc,
+                op: Box::new(op.clone()),
+                reason: "there is no stack frame for locals".to_string(),
+            }),
+        }
+    }
+
+    // local slot index of the innermost stack frame
+    fn local<'a>(vm: &'a mut VM, op: &Operator, index: usize) -> Result<&'a mut u32, VmError> {
+        let pc = vm.program_counter;
+        let locals = &mut frame(vm, op)?.locals;
+        let len = locals.len();
+        match locals.get_mut(index) {
+            Some(local) => Ok(local),
+            None => Err(VmError::OutOfBounds {
+                pc,
+                op: Box::new(op.clone()),
+                index,
+                len,
+            }),
+        }
+    }
+
+    // index in vm.stack of the nth value from the top, or a stack underflow if there are not n + 1 values
+    fn depth(vm: &VM, op: &Operator, n: usize) -> Result<usize, VmError> {
+        match vm.stack.len().checked_sub(n).and_then(|len| len.checked_sub(1)) {
+            Some(index) => Ok(index),
+            None => Err(stack_underflow(vm, op)),
+        }
+    }
+
+    fn stack_underflow(vm: &VM, op: &Operator) -> VmError {
+        VmError::StackUnderflow {
+            pc: vm.program_counter,
+            op: Box::new(op.clone()),
+        }
+    }
+
+    fn out_of_bounds(vm: &VM, op: &Operator, index: usize, len: usize) -> VmError {
+        VmError::OutOfBounds {
+            pc: vm.program_counter,
+            op: Box::new(op.clone()),
+            index,
+            len,
+        }
+    }
+
+    fn divide_by_zero(vm: &VM, op: &Operator) -> VmError {
+        VmError::DivideByZero {
+            pc: vm.program_counter,
+            op: Box::new(op.clone()),
+        }
     }
 
     fn f_to_bytes(f: f32) -> [u8; 4] {
@@ -7,6 +7,8 @@ pub mod ops {
     /// assembler.rs :: read the 8bit opcode and parse the appropriate number of following bytes to create the operator
     /// assembler.rs :: turns the operator into a bytecode stream
     /// asm_parser.rs :: turn the opcodes utf8 name (eg ADD) into the opcode enum (eg Opcode::ADD)
+    /// disassembler.rs :: turn the operator back into its utf8 name and operands
+    /// lowering.rs :: resolve label operands to program counters, for ops that jump to a label
     ///
     /// op_calls can be a noop if it is a special case or NYI
     /// assembler and asm_parser MUST be implemented or you will be unable to :
@@ -25,8 +27,14 @@ pub mod ops {
         // value on stack after this call will be the size of the allocation below
         LOADD(usize),
 
+        // LOADf and LOADi load the first word like LOAD, converted to a float or a signed integer
+        // if the identifier holds a CONST_ of another type (see vm.const_types)
+        LOADf(usize),
+        LOADi(usize),
+
+        // each CONST_ stores 1 word (CONST_S one per 4 bytes) and records its type in vm.const_types
         CONST_U(usize, u32),
-        CONST_F(usize, f32),
+        CONST_F(usize, f32), // stores the bits of the float
         CONST_I(usize, i32),
         CONST_B(usize, bool),
         CONST_S(usize, String),
@@ -34,7 +42,7 @@ pub mod ops {
 
         //preset ptr id, n bytes
         POP,
-        ALLOC(usize, u32),
+        ALLOC(usize, u32), // released when the stack frame it was made in returns or is unwound
         DEALLOC(usize),
         POPS(usize),
         GETLEN(usize),     //number of words
@@ -45,8 +53,22 @@ pub mod ops {
         SETWORD(usize, usize, u32),
 
         DUP,         //duplicate topmost value on stack
-        DUPO(usize), //duplicate value on stack at offset
+        DUPO(usize), //duplicate value on stack at offset, DUPO 1 is the top value
         SWAP,        //swap topmost two values on stack
+        // Forth style stack words, n counts from 0 at the top. they fail with a stack underflow,
+        // leaving the stack as it was, if there are not enough values
+        OVER,        //( a b -- a b a ) duplicate the second value
+        ROT,         //( a b c -- b c a ) move the third value to the top
+        PICK(usize), //duplicate the nth value, PICK 0 is DUP
+        ROLL(usize), //move the nth value to the top, ROLL 1 is SWAP and ROLL 2 is ROT
+        DROP(usize), //pop n values
+        DEPTH,       //push the number of values on the stack
+        CLEAR,       //pop every value
+
+        // local slots of the innermost stack frame, released with the frame by RET or unwinding
+        ENTER(usize),     //give the frame n local slots (new slots are 0)
+        LOCAL_GET(usize), //push local slot i
+        LOCAL_SET(usize), //pop 1 value off stack into local slot i
 
         //i = int, f= float, fi = float<op>int, if = int<op>float
         ADDu,
@@ -75,12 +97,40 @@ pub mod ops {
         MODif,
         MODf,
 
+        // conversions replace the top value, float to integer truncates towards 0 and saturates (NaN is 0)
+        ITOF, // signed integer to float
+        FTOI, // float to signed integer
+        UTOF, // unsigned integer to float
+        // float math on the top value, or the top 2 values for MINf, MAXf and POWf (top ^ below)
+        // floats are compared with CMPf
+        SQRTf,
+        ABSf,
+        FLOORf,
+        CEILf,
+        MINf,
+        MAXf,
+        POWf,
+
         ROR, //rotate right
         ROL, //rotate left
         LSR, //shift right
         ASR, //arithmetic shift right
         LSL, //shift left
         ASL, //arithmetic shift left
+        // shift or rotate by an amount, which is taken modulo 32. the C versions take it as an operand,
+        // the D versions pop it off the stack and then pop the value
+        RORC(u32),
+        ROLC(u32),
+        LSRC(u32),
+        ASRC(u32),
+        LSLC(u32),
+        ASLC(u32),
+        RORD,
+        ROLD,
+        LSRD,
+        ASRD,
+        LSLD,
+        ASLD,
 
         //bitwise ops
         //invert all bits
@@ -91,8 +141,12 @@ pub mod ops {
         NOR,
         NAND,
         CNT, //popcnt, get number of bits set
-        CMP,
+        CLZ, //number of leading zero bits, 32 for 0
+        CTZ, //number of trailing zero bits, 32 for 0
+        CMP, // pop 2 values off stack, push top - below (wrapping), which is 0 if they are equal
         JMP_SCAN, // scans through the program for all LABELS and adds them (and their addresses) to the jmp_label map. may be slow on large programs
+        // every JMP (and DJMP) that is taken pushes a stack frame like CALL. kept for older programs,
+        // use CALL for functions and the BR operators for loops and conditionals
         JMP(String),
         JMPo(String), //jmp if overflow
         //compare all bits. (lop,rop)-> *u32 lop - *u32 rop == 0. cast to uint, sub, compare to 0
@@ -117,10 +171,10 @@ pub mod ops {
         SYSCALL(usize, usize), //system call. left op is syscall id, right op is number of args
         SYSCALLD(usize), //system call. op is syscall id. pop top value off stack, reads as u32. then pops that many off stack as args
 
-        EXCEPT_THROW,         //throw exception
+        EXCEPT_THROW,         //throw exception with code 0
         EXCEPT_CATCH(String), //catch exception - jump to different location. When proceeding normally (not in a stack unwind) this is a noop
 
-        RET, //returns to the location that was last jumped FROM.
+        RET, //closes the innermost stack frame and returns to the operation after the CALL (or JMP) that opened it
 
         EMIT,         //pop top value off stack, emit it to output stream
         EMITS(usize), //emit a string to the output stream
@@ -131,15 +185,89 @@ pub mod ops {
         DJMPe, // pop 2 values off stack, compare. pop 2 values off stack and read as jump pointer. jump if equal
         DJMPne, // pop 2 values off stack, compare, pop 2 values off stack and read as jump pointer., jump if not equal
 
-        DALLOC(usize), // pop 1 value off stack, allocate that many words on the stack. 2 words are pushed onto the stack. This is the pointer to the allocated memory.
-        // DGETSIZE, // pop 2 values off stack, read as alloc pointer. push 1 word onto stack with the size of the allocated memory.
-        // DLOADVALUE, // pop 2 values off stack, read as alloc pointer. push each word of the allocated memory onto the stack, followed by 1 word for its size
-        // DDEALLOC, // pop 2 values off stack, read as alloc pointer. deallocates the memory.
+        DALLOC(usize), // pop 1 value off stack, allocate that many words for the identifier. use DNEW for an allocation addressed by a pointer
+        // heap pointers are 2 words, the high word on top (as DJMP). they address an allocation made by DNEW
+        // an index or value popped with a pointer is below it on the stack
+        DNEW, // pop 1 value off stack, allocate that many words. push the 2 word pointer to the allocated memory
+        DGETSIZE, // pop pointer. push 1 word onto stack with the size of the allocated memory
+        DLOADVALUE, // pop pointer. push each word of the allocated memory onto the stack, followed by 1 word for its size
+        DDEALLOC, // pop pointer. deallocates the memory
+        DGETWORD, // pop pointer, pop index. push the word at index
+        DSETWORD, // pop pointer, pop index, pop value. store value as the word at index
+        DGETBYTE, // pop pointer, pop index. push the byte at index
+        DSETBYTE, // pop pointer, pop index, pop value. store the low byte of value as the byte at index
         LIBLOAD(String),         //load a library (omit .stalib extension)
         DLIBLOAD, //dynamically load a library, pop 1 word, read as number of words, read that many bytes as a string, load library by that string (null bytes at end of decoding are ignored)
         LIBCALL(String, String), //call a library function
         DLIBCALL(String), //dynamically call a library function, decode 1 string from stack to get the library name
         LIBDCALL(String), //dynamically call a library function, decode 1 string from stack to get the function name. argument is library name
         DLIBDCALL, //dynamically call a library function, decode 2 strings from stack. first is library name, second is function name.
+
+        ALLOC_PERSIST(usize, u32), // as ALLOC, but the allocation escapes the stack frame and is kept until DEALLOC
+
+        // calls push a stack frame that RET returns from, to the operation after the call
+        CALL(String), // call a function by label
+        CALLD, // pop 2 values off stack and read as jump pointer (as DJMP). call the function at that location
+        // branches move the program counter without opening a stack frame, for loops and conditionals
+        BR(String), // branch to a label
+        BRo(String), // branch if the last arithmetic operation overflowed
+        BRe(String), // pop 1 value off stack, branch if it is 0 (equal after a CMP)
+        BRne(String), // pop 1 value off stack, branch if it is not 0
+        BRs(String, String), // pop 1 value off stack, branch to the first label if it is 0, otherwise the second
+        DBR, // pop 2 values off stack and read as jump pointer. branch to that location
+        DBRe, // pop 2 values off stack, compare. pop 2 values off stack and read as jump pointer. branch if equal
+        DBRne, // pop 2 values off stack, compare. pop 2 values off stack and read as jump pointer. branch if not equal
+
+        // exceptions unwind to the nearest catch (or finally) before the throw, see vm.handlers
+        EXCEPT_THROWC(u32), // throw exception with a code
+        EXCEPT_THROWD, // pop 1 value off stack, throw exception with it as the code
+        EXCEPT_THROWP(u32, usize), // throw exception with a code and the allocation of an identifier as its payload
+        EXCEPT_CATCH_RANGE(String, u32, u32), // as EXCEPT_CATCH, only for codes from left to right (inclusive)
+        EXCEPT_FINALLY(String), // runs the block at the label for every exception, which carries on unwinding at the EXCEPT_END
+        EXCEPT_END, // ends a finally block. noop unless the block was entered by an exception
+        EXCEPT_CODE, // push the code of the exception being handled
+        EXCEPT_PAYLOAD(usize), // give the payload of the exception being handled the identifier, owned by the current frame
+
+        // compares pop 2 values off stack and set vm.signal_compare to how the top value orders against the one below it
+        CMPu, // compare as unsigned integers
+        CMPi, // compare as signed integers
+        CMPf, // compare as floats. a NaN is unordered and clears signal_compare, so no conditional is taken
+        // jump on the last compare: top < below, top > below, top <= below, top >= below. taken JMPs push a stack frame like JMP
+        JMPlt(String),
+        JMPgt(String),
+        JMPle(String),
+        JMPge(String),
+        // branch on the last compare, as the JMPs above without the stack frame
+        BRlt(String),
+        BRgt(String),
+        BRle(String),
+        BRge(String),
+
+        // wide arithmetic on vm.registers, which hold the 64 bit registers 0 and 1 (see VM::register64),
+        // or are 128 bit register 0 with vm._128_registers as register 1. results go to register 0
+        SETREG64(usize), // pop 2 values off stack (high word on top) into the 64 bit register
+        GETREG64(usize), // push the 64 bit register, low word first so the high word is on top
+        SETREG128(usize), // pop 4 values off stack (most significant word on top) into the 128 bit register
+        GETREG128(usize), // push the 128 bit register, least significant word first
+        ADD64u, // register 0 + register 1
+        ADD64i,
+        SUB64u, // register 0 - register 1
+        SUB64i,
+        MUL64u,
+        MUL64i,
+        DIV64u, // register 0 / register 1
+        DIV64i,
+        LSL64, // pop 1 value off stack, shift register 0 by it (modulo 64)
+        LSR64,
+        ASR64,
+        CMP64u, // set signal_compare to how register 0 orders against register 1, as CMPu
+        CMP64i,
+        ADD128u,
+        SUB128u,
+        MUL128u,
+        DIV128u,
+        LSL128, // pop 1 value off stack, shift register 0 by it (modulo 128)
+        LSR128,
+        CMP128u,
     }
 }
@@ -1,10 +1,14 @@
 pub mod stal_dll {
-    use crate::assembler::assembler::parse_binary;
+    use crate::assembler::assembler::{try_parse_binary, DecodeError};
+    use crate::fuel::fuel::RunOutcome;
+    use crate::limits::limits::Limits;
     use crate::stalfos::ops::Operator;
-    use crate::stalfos::VM;
+    use crate::stalfos::{StackFrame, VM};
+    use crate::vm_error::vm_error::VmError;
     use std::borrow::BorrowMut;
     use std::collections::{BTreeMap, HashMap};
     use std::env::{current_dir, set_current_dir};
+    use std::fmt;
     use std::fs::File;
     use std::io::Read;
 
@@ -23,12 +27,50 @@ pub mod stal_dll {
         pub alloc_table: BTreeMap<usize, (usize, u32)>,
     }
 
-    pub fn load_library(namespace: &str) -> StalDynamicLibrary {
-        let path = format!("{}.stalib", namespace);
-        let mut file = File::open(path).unwrap();
+    /// a library file that could not be read or is not a valid binary
+    #[derive(Debug, Clone)]
+    pub enum LibraryLoadError {
+        Io { path: String, message: String },
+        Decode { path: String, error: DecodeError },
+    }
+
+    impl fmt::Display for LibraryLoadError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                LibraryLoadError::Io { path, message } => {
+                    write!(f, "could not read {}: {}", path, message)
+                }
+                LibraryLoadError::Decode { path, error } => {
+                    write!(f, "{} is corrupt: {}", path, error)
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for LibraryLoadError {}
+
+    fn read_library_file(path: &str) -> Result<(Vec<Operator>, String), LibraryLoadError> {
         let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer).unwrap();
-        let (program, namespace) = parse_binary(buffer);
+        let read = File::open(path).and_then(|mut file| file.read_to_end(&mut buffer));
+        if let Err(error) = read {
+            return Err(LibraryLoadError::Io {
+                path: path.to_string(),
+                message: error.to_string(),
+            });
+        }
+
+        match try_parse_binary(buffer) {
+            Ok(result) => Ok(result),
+            Err(error) => Err(LibraryLoadError::Decode {
+                path: path.to_string(),
+                error,
+            }),
+        }
+    }
+
+    pub fn load_library(namespace: &str) -> Result<StalDynamicLibrary, LibraryLoadError> {
+        let path = format!("{}.stalib", namespace);
+        let (program, namespace) = read_library_file(&path)?;
 
         let mut jump_table = HashMap::new();
         for (_, op) in program.iter().enumerate() {
@@ -44,18 +86,18 @@ pub mod stal_dll {
                 _ => {}
             }
         }
-        StalDynamicLibrary {
+        Ok(StalDynamicLibrary {
             namespace: namespace.to_string(),
             operations: program,
             jump_table,
-        }
+        })
     }
 
-    pub fn load_file_as_library(path: &str, as_namespace: &str) -> StalDynamicLibrary {
-        let mut file = File::open(path).unwrap();
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer).unwrap();
-        let (program, _) = parse_binary(buffer);
+    pub fn load_file_as_library(
+        path: &str,
+        as_namespace: &str,
+    ) -> Result<StalDynamicLibrary, LibraryLoadError> {
+        let (program, _) = read_library_file(path)?;
 
         let mut jump_table = HashMap::new();
         for (_, op) in program.iter().enumerate() {
@@ -71,11 +113,11 @@ pub mod stal_dll {
                 _ => {}
             }
         }
-        StalDynamicLibrary {
+        Ok(StalDynamicLibrary {
             namespace: as_namespace.to_string(),
             operations: program,
             jump_table,
-        }
+        })
     }
 
     impl StalDynamicLibrary {
@@ -113,19 +155,11 @@ pub mod stal_dll {
             VM {
                 program: v,
                 stack: self.stack.clone(),
-                program_counter: 0,
                 memory: self.memory.clone(),
                 static_alloc_table: self.alloc_table.clone(),
-                signal_overflow: false,
-                signal_finished: false,
                 jmp_table: self.lib.jump_table.clone(),
-                stack_frame_pointers: Vec::new(),
-                output: Vec::new(),
-                signal_debug: false,
                 is_lib: true,
-                registers: [0; 16],
-                _128_registers: [0; 16],
-                dynamic_allocations: vec![],
+                ..VM::new()
             }
         }
 
@@ -134,22 +168,66 @@ pub mod stal_dll {
             name: String,
             arg_stack: Vec<u32>,
             libs: &mut HashMap<String, StalDynamicLibrary>,
-        ) -> Vec<u32> {
+            caller: &mut VM,
+        ) -> Result<Vec<u32>, VmError> {
             let mut ret: Vec<u32> = vec![];
 
-            let jump_location = *self.lib.jump_table.get(&name).unwrap();
+            let jump_location = match self.lib.jump_table.get(&name) {
+                Some(location) => *location,
+                None => {
+                    return Err(VmError::UnknownLabel {
+                        pc: 0,
+                        op: Box::new(Operator::LIBCALL(self.lib.namespace.clone(), name.clone())),
+                        label: name,
+                    })
+                }
+            };
             self.stack.extend_from_slice(&arg_stack);
             let mut vm = self.pack_as_vm();
-            vm.prepare();
+            vm.prepare()?;
             vm.program_counter = jump_location;
-            vm.run_with_libs(libs);
+            // the function's frame, for its ENTER and allocations. its RET closes it and ends the call
+            vm.stack_frame_pointers.push(StackFrame::new(0, jump_location));
+
+            // the library uses the caller's syscalls and output sinks, they are handed back even if it faults
+            // its memory and locals come out of what the caller has left, so nested calls share one budget
+            let used = caller.memory.len() + caller.local_words;
+            vm.limits = Limits {
+                max_memory_words: caller.limits.max_memory_words.saturating_sub(used),
+                ..caller.limits
+            };
+            vm.fuel_costs = caller.fuel_costs.clone();
+            swap_host(caller, &mut vm);
+            let result = match caller.fuel.as_mut() {
+                // called inside run_for, the library runs on (and uses up) the caller's fuel
+                Some(fuel) => match vm.run_metered(libs, fuel) {
+                    RunOutcome::Finished => Ok(()),
+                    RunOutcome::OutOfFuel => Err(VmError::OutOfFuel {
+                        pc: vm.program_counter,
+                        // run_metered only runs out of fuel on an operation in the program
+                        op: Box::new(vm.program[vm.program_counter].clone()),
+                    }),
+                    RunOutcome::Trapped(error) => Err(error),
+                },
+                None => vm.run_with_libs(libs).map(|_| ()),
+            };
+            swap_host(caller, &mut vm);
+            result?;
 
             let allocation_size = vm.stack.pop();
             if allocation_size != None {
                 //copy last n bytes of stack to ret
                 let mut i = 0;
                 while i < allocation_size.unwrap() {
-                    ret.push(vm.stack.pop().unwrap());
+                    match vm.stack.pop() {
+                        Some(v) => ret.push(v),
+                        None => {
+                            return Err(VmError::StackUnderflow {
+                                pc: vm.program_counter,
+                                op: Box::new(Operator::RET),
+                            })
+                        }
+                    }
                     i += 1;
                 }
 
@@ -157,7 +235,13 @@ pub mod stal_dll {
                 ret.push(allocation_size.unwrap() as u32);
             }
 
-            return ret;
+            return Ok(ret);
         }
     }
+
+    fn swap_host(caller: &mut VM, library: &mut VM) {
+        std::mem::swap(&mut caller.syscalls, &mut library.syscalls);
+        std::mem::swap(&mut caller.console, &mut library.console);
+        std::mem::swap(&mut caller.trace, &mut library.trace);
+    }
 }
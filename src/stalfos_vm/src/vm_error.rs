@@ -0,0 +1,162 @@
+pub mod vm_error {
+    use crate::exceptions::exceptions;
+    use crate::ops::ops::Operator;
+    use crate::stal_dll::stal_dll::LibraryLoadError;
+    use std::fmt;
+
+    ///
+    /// Runtime failures raised by the VM. Every variant produced while executing an operator
+    /// carries the program counter and the operator that failed, so an embedder can report the
+    /// fault without the whole host process going down.
+    ///
+    #[derive(Debug, Clone)]
+    pub enum VmError {
+        // popped (or peeked) a value off an empty stack
+        StackUnderflow { pc: usize, op: Box<Operator> },
+        // jumped to a label that is not in the jmp_table (or a library function that does not exist)
+        UnknownLabel { pc: usize, op: Box<Operator>, label: String },
+        // referenced an allocation identifier that is not in the static_alloc_table
+        UnknownAllocation { pc: usize, op: Box<Operator>, ptr: usize },
+        // a heap pointer that is not the start of a live DNEW allocation, eg one that was already freed
+        InvalidPointer { pc: usize, op: Box<Operator>, pointer: u64 },
+        // indexed past the end of an allocation, memory or the program
+        OutOfBounds { pc: usize, op: Box<Operator>, index: usize, len: usize },
+        DivideByZero { pc: usize, op: Box<Operator> },
+        // LIBCALL and friends on a library that has not been loaded with LIBLOAD
+        UnknownLibrary { pc: usize, op: Box<Operator>, library: String },
+        // LIBLOAD or DLIBLOAD could not read or decode the .stalib file
+        LibraryLoad { pc: usize, op: Box<Operator>, library: String, error: LibraryLoadError },
+        // a library function failed. pc and op are the call site, error is the fault inside the library
+        LibraryFault { pc: usize, op: Box<Operator>, library: String, error: Box<VmError> },
+        // SYSCALL or SYSCALLD with an id that has no registered handler
+        UnknownSyscall { pc: usize, op: Box<Operator>, syscall_id: usize },
+        // the guest called the panic syscall
+        GuestPanic { pc: usize, op: Box<Operator>, code: u32 },
+        // an exception unwound every frame without finding a catch for its code. pc is where it was thrown
+        Uncaught { code: u32, pc: usize },
+        // the operator cannot be executed at this point, eg a JMP_DEF after other instructions
+        InvalidOperation { pc: usize, op: Box<Operator>, reason: String },
+        // the program counter moved outside of the program, eg by falling off the end or a bad DJMP
+        InvalidProgramCounter { pc: usize },
+        // an allocation or ENTER would take memory and locals past limits.max_memory_words
+        MemoryLimit { pc: usize, op: Box<Operator>, requested: usize, limit: usize },
+        // the stack grew past limits.max_stack_depth
+        StackOverflow { pc: usize, op: Box<Operator>, limit: usize },
+        // stack_frame_pointers grew past limits.max_frame_depth
+        FrameOverflow { pc: usize, op: Box<Operator>, limit: usize },
+        // a library function used up the fuel left in run_for. op is the one it could not afford
+        OutOfFuel { pc: usize, op: Box<Operator> },
+        // writing to the console or trace sink failed
+        Output { pc: usize, message: String },
+        // prepare() could not find a main label in a program that is not a library
+        NoMain,
+    }
+
+    impl VmError {
+        ///
+        /// The exception code this fault is thrown as when vm.trap_faults is set. None for errors
+        /// a guest cannot recover from (or that it raised itself), which always stop the VM.
+        ///
+        pub fn fault_code(&self) -> Option<u32> {
+            match self {
+                VmError::DivideByZero { .. } => Some(exceptions::DIVIDE_BY_ZERO),
+                VmError::OutOfBounds { .. } => Some(exceptions::OUT_OF_BOUNDS),
+                VmError::StackUnderflow { .. } => Some(exceptions::STACK_UNDERFLOW),
+                VmError::UnknownAllocation { .. } => Some(exceptions::UNKNOWN_ALLOCATION),
+                VmError::InvalidPointer { .. } => Some(exceptions::INVALID_POINTER),
+                VmError::UnknownLabel { .. } => Some(exceptions::UNKNOWN_LABEL),
+                VmError::UnknownSyscall { .. } => Some(exceptions::UNKNOWN_SYSCALL),
+                // running out of fuel cannot be caught, the fuel is gone either way
+                VmError::LibraryFault { error, .. } if matches!(**error, VmError::OutOfFuel { .. }) => None,
+                VmError::UnknownLibrary { .. }
+                | VmError::LibraryLoad { .. }
+                | VmError::LibraryFault { .. } => Some(exceptions::LIBRARY_FAULT),
+                VmError::MemoryLimit { .. } => Some(exceptions::MEMORY_LIMIT),
+                _ => None,
+            }
+        }
+    }
+
+    impl fmt::Display for VmError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                VmError::StackUnderflow { pc, op } => {
+                    write!(f, "stack underflow at {} ({:?})", pc, op)
+                }
+                VmError::UnknownLabel { pc, op, label } => {
+                    write!(f, "unknown label '{}' at {} ({:?})", label, pc, op)
+                }
+                VmError::UnknownAllocation { pc, op, ptr } => {
+                    write!(f, "unknown allocation {} at {} ({:?})", ptr, pc, op)
+                }
+                VmError::InvalidPointer { pc, op, pointer } => {
+                    write!(f, "invalid heap pointer {:#x} at {} ({:?})", pointer, pc, op)
+                }
+                VmError::OutOfBounds { pc, op, index, len } => {
+                    write!(
+                        f,
+                        "index {} out of bounds for length {} at {} ({:?})",
+                        index, len, pc, op
+                    )
+                }
+                VmError::DivideByZero { pc, op } => {
+                    write!(f, "divide by zero at {} ({:?})", pc, op)
+                }
+                VmError::UnknownLibrary { pc, op, library } => {
+                    write!(f, "library '{}' not loaded at {} ({:?})", library, pc, op)
+                }
+                VmError::LibraryLoad { pc, op, library, error } => {
+                    write!(
+                        f,
+                        "could not load library '{}' at {} ({:?}): {}",
+                        library, pc, op, error
+                    )
+                }
+                VmError::LibraryFault { pc, op, library, error } => {
+                    write!(
+                        f,
+                        "library '{}' failed: {}, called at {} ({:?})",
+                        library, error, pc, op
+                    )
+                }
+                VmError::UnknownSyscall { pc, op, syscall_id } => {
+                    write!(f, "unknown syscall {} at {} ({:?})", syscall_id, pc, op)
+                }
+                VmError::GuestPanic { pc, op, code } => {
+                    write!(f, "VM called a panic! with code {} at {} ({:?})", code, pc, op)
+                }
+                VmError::Uncaught { code, pc } => {
+                    write!(f, "uncaught exception {} thrown at {}", code, pc)
+                }
+                VmError::InvalidOperation { pc, op, reason } => {
+                    write!(f, "invalid operation at {} ({:?}): {}", pc, op, reason)
+                }
+                VmError::InvalidProgramCounter { pc } => {
+                    write!(f, "program counter {} is outside of the program", pc)
+                }
+                VmError::MemoryLimit { pc, op, requested, limit } => {
+                    write!(
+                        f,
+                        "allocating {} words would exceed the memory limit of {} words at {} ({:?})",
+                        requested, limit, pc, op
+                    )
+                }
+                VmError::StackOverflow { pc, op, limit } => {
+                    write!(f, "stack exceeded {} values at {} ({:?})", limit, pc, op)
+                }
+                VmError::FrameOverflow { pc, op, limit } => {
+                    write!(f, "stack frames exceeded {} at {} ({:?})", limit, pc, op)
+                }
+                VmError::OutOfFuel { pc, op } => {
+                    write!(f, "out of fuel at {} ({:?})", pc, op)
+                }
+                VmError::Output { pc, message } => {
+                    write!(f, "could not write output at {}: {}", pc, message)
+                }
+                VmError::NoMain => write!(f, "No main function found"),
+            }
+        }
+    }
+
+    impl std::error::Error for VmError {}
+}
@@ -0,0 +1,130 @@
+use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::stalfos::{Allocator, VM};
+
+// small deterministic generator so failures are reproducible
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % bound
+    }
+}
+
+fn assert_no_overlap(blocks: &mut Vec<(usize, usize)>) {
+    blocks.sort();
+    for pair in blocks.windows(2) {
+        let (start, size) = pair[0];
+        assert!(start + size <= pair[1].0, "{:?} overlaps {:?}", pair[0], pair[1]);
+    }
+}
+
+#[test]
+fn allocations_never_overlap() {
+    for seed in 0..50 {
+        let mut rng = Lcg(seed);
+        let mut allocator = Allocator::new();
+        let mut memory = vec![];
+        let mut live: Vec<(usize, usize)> = vec![];
+
+        for _ in 0..500 {
+            if live.len() > 0 && rng.next(3) == 0 {
+                let (start, size) = live.swap_remove(rng.next(live.len()));
+                assert!(allocator.free(start, size));
+            } else {
+                let size = rng.next(16);
+                let start = allocator.allocate(size, &mut memory, usize::MAX).unwrap();
+                assert!(start + size <= memory.len());
+                assert!(memory[start..start + size].iter().all(|w| *w == 0));
+                // dirty the block so a later overlapping allocation would not read back zeroes
+                for word in memory[start..start + size].iter_mut() {
+                    *word = 0xFFFF_FFFF;
+                }
+                if size > 0 {
+                    live.push((start, size));
+                }
+            }
+
+            let mut blocks = live.clone();
+            blocks.extend(allocator.free_blocks());
+            assert_no_overlap(&mut blocks);
+        }
+    }
+}
+
+#[test]
+fn free_blocks_coalesce() {
+    let mut allocator = Allocator::new();
+    let mut memory = vec![];
+    let a = allocator.allocate(4, &mut memory, usize::MAX).unwrap();
+    let b = allocator.allocate(4, &mut memory, usize::MAX).unwrap();
+    let c = allocator.allocate(4, &mut memory, usize::MAX).unwrap();
+
+    allocator.free(a, 4);
+    allocator.free(c, 4);
+    assert_eq!(allocator.free_blocks(), vec![(0, 4), (8, 4)]);
+
+    allocator.free(b, 4);
+    assert_eq!(allocator.free_blocks(), vec![(0, 12)]);
+
+    // reuses the coalesced block instead of growing memory
+    assert_eq!(allocator.allocate(10, &mut memory, usize::MAX), Some(0));
+    assert_eq!(memory.len(), 12);
+}
+
+#[test]
+fn grows_into_a_free_block_at_the_end() {
+    let mut allocator = Allocator::new();
+    let mut memory = vec![];
+    allocator.allocate(2, &mut memory, usize::MAX).unwrap();
+    let b = allocator.allocate(2, &mut memory, usize::MAX).unwrap();
+    allocator.free(b, 2);
+
+    assert_eq!(allocator.allocate(5, &mut memory, usize::MAX), Some(2));
+    assert_eq!(memory.len(), 7);
+    assert_eq!(allocator.allocate(1, &mut memory, 7), None);
+}
+
+#[test]
+fn alloc_and_dealloc_reuse_memory() {
+    let mut rng = Lcg(7);
+    let mut source = String::from("JMP_SCAN\n.main\n");
+    let mut live = std::collections::HashSet::new();
+    for _ in 0..400 {
+        let id = rng.next(20);
+        match rng.next(4) {
+            // DEALLOC of an identifier that is not allocated is an error
+            0 if live.remove(&id) => source.push_str(&format!("DEALLOC {}\n", id)),
+            0 => {}
+            1 => {
+                live.insert(id);
+                source.push_str(&format!("CONST_S {} \"{}\"\n", id, "x".repeat(rng.next(40))));
+            }
+            _ => {
+                live.insert(id);
+                source.push_str(&format!("ALLOC {} {}\n", id, rng.next(12)));
+            }
+        }
+    }
+
+    let mut vm = VM::new();
+    let (_, ops) = parse_string(source).unwrap();
+    vm.add_ops(ops).prepare().unwrap();
+    loop {
+        vm.step(&mut Default::default()).unwrap();
+        if vm.program_counter >= vm.program.len() {
+            break;
+        }
+
+        let mut blocks: Vec<(usize, usize)> = vm
+            .static_alloc_table
+            .values()
+            .map(|(start, size)| (*start, *size as usize))
+            .filter(|(_, size)| *size > 0)
+            .collect();
+        assert_no_overlap(&mut blocks);
+    }
+
+    // at most 20 identifiers of at most 11 words are live at once
+    assert!(vm.memory.len() <= 20 * 11 * 2, "memory grew to {}", vm.memory.len());
+}
@@ -0,0 +1,68 @@
+use stalfos_vm::asm_parser::asm_parser::{parse_source, parse_string, Diagnostic};
+
+fn diagnostics(source: &str) -> Vec<Diagnostic> {
+    parse_string(source.to_string()).unwrap_err()
+}
+
+#[test]
+fn points_at_the_offending_token() {
+    let missing = &diagnostics("JMP_SCAN\nPUSH\n")[0];
+    assert_eq!((missing.line, missing.column), (2, 1));
+    assert_eq!(missing.token, "PUSH");
+    assert_eq!(missing.message, "missing operand for PUSH");
+    assert_eq!(missing.hint, "expected 1 operand for PUSH");
+
+    let invalid = &diagnostics("JMP_SCAN\n.main\n    PUSH abc\n")[0];
+    assert_eq!((invalid.line, invalid.column), (3, 10));
+    assert_eq!(invalid.token, "abc");
+    assert_eq!(invalid.message, "invalid operand 'abc'");
+    assert_eq!(invalid.hint, "expected a 32 bit unsigned integer for PUSH");
+    assert_eq!(invalid.source_line, "    PUSH abc");
+
+    let extra = &diagnostics("POP 1\n")[0];
+    assert_eq!((extra.line, extra.column), (1, 5));
+    assert_eq!(extra.token, "1");
+    assert_eq!(extra.hint, "expected 0 operands for POP");
+
+    let unknown = &diagnostics("JMP_SCAN ; comment\nNOPE\n")[0];
+    assert_eq!((unknown.line, unknown.column), (2, 1));
+    assert_eq!(unknown.token, "NOPE");
+    assert_eq!(unknown.hint, "unknown operation");
+}
+
+#[test]
+fn sorts_diagnostics_by_position() {
+    // the unterminated string is found while tokenizing, before PUSH x is parsed
+    let found = diagnostics("PUSH x\nPOP POP\nCONST_S 1 \"abc\n");
+    let positions: Vec<(usize, usize)> = found.iter().map(|d| (d.line, d.column)).collect();
+    assert_eq!(positions, vec![(1, 6), (2, 5), (3, 11)]);
+    assert_eq!(found[2].message, "unterminated string");
+}
+
+#[test]
+fn reports_a_lone_quote_without_panicking() {
+    let found = diagnostics("CONST_S 1 \"");
+    assert_eq!(found.len(), 1);
+    assert_eq!((found[0].line, found[0].column), (1, 11));
+    assert_eq!(found[0].message, "unterminated string");
+}
+
+#[test]
+fn renders_a_caret_under_the_token() {
+    let diagnostic = &parse_source("main.sta", "JMP_SCAN\n  PUSH abc\n").unwrap_err()[0];
+    assert_eq!(
+        diagnostic.to_string(),
+        "error: invalid operand 'abc'\n \
+         --> main.sta:2:8\n  \
+         |\n\
+         2 |   PUSH abc\n  \
+         |        ^^^ expected a 32 bit unsigned integer for PUSH"
+    );
+
+    // tabs are kept so the caret lines up however wide the terminal draws them
+    let diagnostic = &diagnostics("\tSETBYTE 1 0\n")[0];
+    assert_eq!(
+        diagnostic.to_string().lines().last().unwrap(),
+        "  | \t          ^ expected 3 operands for SETBYTE"
+    );
+}
@@ -0,0 +1,285 @@
+use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::assembler::assembler::{
+    assemble, try_parse_binary, DecodeError, DecodeErrorKind, OperandKind, FORMAT_VERSION, VERSION_MARKER,
+};
+use stalfos_vm::stalfos::ops::Operator;
+
+const PROGRAM_MAGIC: [u8; 4] = [0xDE, 0xAD, 0xFA, 0xCE];
+const LIBRARY_MAGIC: [u8; 4] = [0xDE, 0xAD, 0xC0, 0xDE];
+
+// a version 1 program header followed by bytes
+fn program(bytes: &[u8]) -> Vec<u8> {
+    let mut binary = PROGRAM_MAGIC.to_vec();
+    binary.extend_from_slice(&[VERSION_MARKER, FORMAT_VERSION]);
+    binary.extend_from_slice(bytes);
+    binary
+}
+
+fn decode_error(binary: Vec<u8>) -> DecodeError {
+    try_parse_binary(binary).unwrap_err()
+}
+
+fn error(
+    offset: usize,
+    expected: OperandKind,
+    opcode: Option<u8>,
+    kind: DecodeErrorKind,
+) -> DecodeError {
+    DecodeError {
+        offset,
+        expected,
+        opcode,
+        kind,
+    }
+}
+
+#[test]
+fn reports_truncated_headers() {
+    use DecodeErrorKind::UnexpectedEnd;
+
+    assert_eq!(
+        decode_error(vec![]),
+        error(0, OperandKind::Magic, None, UnexpectedEnd)
+    );
+    assert_eq!(
+        decode_error(vec![0xDE, 0xAD]),
+        error(2, OperandKind::Magic, None, UnexpectedEnd)
+    );
+
+    // the namespace length, then the namespace itself
+    let mut library = LIBRARY_MAGIC.to_vec();
+    library.extend_from_slice(&[VERSION_MARKER, FORMAT_VERSION, 0, 0, 0]);
+    assert_eq!(
+        decode_error(library),
+        error(6, OperandKind::Usize, None, UnexpectedEnd)
+    );
+
+    let mut library = LIBRARY_MAGIC.to_vec();
+    library.extend_from_slice(&[
+        VERSION_MARKER,
+        FORMAT_VERSION,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        5,
+        b'a',
+        b'b',
+    ]);
+    assert_eq!(
+        decode_error(library),
+        error(14, OperandKind::String, None, UnexpectedEnd)
+    );
+}
+
+#[test]
+fn reports_truncated_operands() {
+    use DecodeErrorKind::UnexpectedEnd;
+
+    // PUSH with half of its u32
+    assert_eq!(
+        decode_error(program(&[0x01, 0x00, 0x00])),
+        error(7, OperandKind::U32, Some(0x01), UnexpectedEnd)
+    );
+    // CONST_U with its usize but without its u32, after a complete PUSH
+    let mut bytes = vec![0x01, 0, 0, 0, 7, 0x04];
+    bytes.extend_from_slice(&1u64.to_be_bytes());
+    assert_eq!(
+        decode_error(program(&bytes)),
+        error(20, OperandKind::U32, Some(0x04), UnexpectedEnd)
+    );
+    // CALL with a string length prefix that stops short
+    assert_eq!(
+        decode_error(program(&[0x62, 0, 0, 0])),
+        error(7, OperandKind::Usize, Some(0x62), UnexpectedEnd)
+    );
+}
+
+#[test]
+fn reports_bad_magic() {
+    assert_eq!(
+        decode_error(vec![0xDE, 0xAD, 0xBE, 0xEF, 0x01, 0, 0, 0, 1]),
+        error(0, OperandKind::Magic, None, DecodeErrorKind::InvalidValue)
+    );
+}
+
+#[test]
+fn reports_unknown_opcodes() {
+    let error = decode_error(program(&[0x01, 0, 0, 0, 1, 0xFE]));
+    assert_eq!(
+        error,
+        self::error(
+            11,
+            OperandKind::Opcode,
+            Some(0xFE),
+            DecodeErrorKind::UnknownOpcode
+        )
+    );
+    assert_eq!(
+        error.to_string(),
+        "unknown opcode at byte 11: expected Opcode while decoding opcode 0xFE"
+    );
+}
+
+#[test]
+fn reports_invalid_bools() {
+    // CONST_B 1 with 0x01, bools are 0x00 or 0xff
+    let mut bytes = vec![0x07];
+    bytes.extend_from_slice(&1u64.to_be_bytes());
+    bytes.push(0x01);
+    assert_eq!(
+        decode_error(program(&bytes)),
+        error(
+            15,
+            OperandKind::Bool,
+            Some(0x07),
+            DecodeErrorKind::InvalidValue
+        )
+    );
+
+    bytes[9] = 0xFF;
+    let (ops, _) = try_parse_binary(program(&bytes)).unwrap();
+    assert_eq!(
+        format!("{:?}", ops),
+        format!("{:?}", vec![Operator::CONST_B(1, true)])
+    );
+}
+
+#[test]
+fn reports_huge_string_lengths_without_allocating() {
+    // CALL with a u64::MAX length prefix
+    let mut bytes = vec![0x62];
+    bytes.extend_from_slice(&u64::MAX.to_be_bytes());
+    bytes.extend_from_slice(b"main");
+    let expected = if usize::BITS < 64 {
+        error(
+            7,
+            OperandKind::Usize,
+            Some(0x62),
+            DecodeErrorKind::InvalidValue,
+        )
+    } else {
+        error(
+            15,
+            OperandKind::String,
+            Some(0x62),
+            DecodeErrorKind::UnexpectedEnd,
+        )
+    };
+    assert_eq!(decode_error(program(&bytes)), expected);
+
+    // and as a library namespace length
+    let mut library = LIBRARY_MAGIC.to_vec();
+    library.extend_from_slice(&[VERSION_MARKER, FORMAT_VERSION]);
+    library.extend_from_slice(&(u32::MAX as u64).to_be_bytes());
+    assert_eq!(decode_error(library).kind, DecodeErrorKind::UnexpectedEnd);
+}
+
+#[test]
+fn round_trips_versioned_binaries() {
+    let source = "#<lib>\nJMP_DEF f 2\n.JT_END\n\
+        .f\nLOAD 3\nCONST_S 1 \"hi\"\nCONST_F 2 1.5\nCONST_I 3 -4\nBRs f f\nRET\n";
+    let (namespace, ops) = parse_string(source.to_string()).unwrap();
+    let binary = assemble(&ops, namespace);
+    assert_eq!(binary[..6], [0xDE, 0xAD, 0xC0, 0xDE, VERSION_MARKER, FORMAT_VERSION]);
+
+    let (decoded, namespace) = try_parse_binary(binary).unwrap();
+    assert_eq!(namespace, "lib");
+    assert_eq!(format!("{:?}", decoded), format!("{:?}", ops));
+}
+
+#[test]
+fn writes_usize_operands_as_8_bytes_on_any_host() {
+    let binary = assemble(&vec![Operator::LOAD(3)], String::new());
+    // the opcode and a big endian u64 after the header
+    assert_eq!(binary, program(&[0x02, 0, 0, 0, 0, 0, 0, 0, 3]));
+}
+
+// the original layout has no version, and usize operands are as wide as the host's usize
+fn host_usize(value: usize) -> Vec<u8> {
+    value.to_be_bytes().to_vec()
+}
+
+#[test]
+fn reads_unversioned_programs() {
+    let mut binary = PROGRAM_MAGIC.to_vec();
+    binary.extend_from_slice(&[0x01, 0, 0, 0, 7]);
+    binary.push(0x02);
+    binary.extend(host_usize(3));
+    binary.push(0x62);
+    binary.extend(host_usize(4));
+    binary.extend_from_slice(b"main");
+    binary.push(0x1A);
+
+    let (ops, namespace) = try_parse_binary(binary).unwrap();
+    assert_eq!(namespace, "");
+    assert_eq!(
+        format!("{:?}", ops),
+        format!(
+            "{:?}",
+            vec![
+                Operator::PUSH(7),
+                Operator::LOAD(3),
+                Operator::CALL("main".to_string()),
+                Operator::RET
+            ]
+        )
+    );
+}
+
+#[test]
+fn reads_unversioned_libraries() {
+    let mut binary = LIBRARY_MAGIC.to_vec();
+    binary.extend(host_usize(3));
+    binary.extend_from_slice(b"lib");
+    binary.push(0x14);
+    binary.extend(host_usize(1));
+    binary.extend_from_slice(b"f");
+    binary.extend(host_usize(2));
+    binary.push(0x1A);
+
+    let (ops, namespace) = try_parse_binary(binary).unwrap();
+    assert_eq!(namespace, "lib");
+    assert_eq!(
+        format!("{:?}", ops),
+        format!(
+            "{:?}",
+            vec![Operator::JMP_DEF("f".to_string(), 2), Operator::RET]
+        )
+    );
+}
+
+#[test]
+fn rejects_unsupported_versions() {
+    let mut binary = PROGRAM_MAGIC.to_vec();
+    binary.extend_from_slice(&[VERSION_MARKER, FORMAT_VERSION + 1, 0x1A]);
+    let unsupported = decode_error(binary);
+    assert_eq!(
+        unsupported,
+        error(
+            5,
+            OperandKind::Version,
+            None,
+            DecodeErrorKind::UnsupportedVersion
+        )
+    );
+    assert_eq!(
+        unsupported.to_string(),
+        "unsupported format version at byte 5: expected Version"
+    );
+
+    let mut binary = PROGRAM_MAGIC.to_vec();
+    binary.push(VERSION_MARKER);
+    assert_eq!(
+        decode_error(binary),
+        error(
+            5,
+            OperandKind::Version,
+            None,
+            DecodeErrorKind::UnexpectedEnd
+        )
+    );
+}
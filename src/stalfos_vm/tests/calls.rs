@@ -0,0 +1,93 @@
+mod common;
+
+use common::run;
+use stalfos_vm::stalfos::VM;
+
+#[test]
+fn ret_resumes_after_the_call() {
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        CALL one
+        PUSH 3
+        SYSCALL 2 0
+        .one
+        PUSH 1
+        CALL two
+        RET
+        .two
+        PUSH 2
+        RET
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![1, 2, 3]);
+    assert_eq!(vm.stack_frame_pointers.len(), 1);
+}
+
+#[test]
+fn branch_loops_do_not_open_frames() {
+    let mut vm = VM::new();
+    vm.limits.max_frame_depth = 2;
+    run(
+        &mut vm,
+        "
+        PUSH 0
+        PUSH 1000
+        .loop
+        ; count the iterations below the counter
+        SWAP
+        PUSH 1
+        ADDu
+        SWAP
+        PUSH 1
+        SWAP
+        SUBu
+        DUP
+        BRne loop
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![1000, 0]);
+    assert_eq!(vm.stack_frame_pointers.len(), 1);
+}
+
+#[test]
+fn calld_and_dbr_use_a_two_word_address() {
+    let mut vm = VM::new();
+    // 0 JMP_SCAN, 1 main, 2-4 call, 5-7 branch, 8 exit, 9 function, 10-11 branch target
+    run(
+        &mut vm,
+        "
+        PUSH 9
+        PUSH 0
+        CALLD
+        PUSH 11
+        PUSH 0
+        DBR
+        SYSCALL 2 0
+        .function
+        RET
+        .target
+        PUSH 7
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![7]);
+    assert_eq!(vm.stack_frame_pointers.len(), 1);
+}
+
+#[test]
+fn ret_from_main_finishes() {
+    let mut vm = VM::new();
+    run(&mut vm, "PUSH 1\nRET\nPUSH 2\n").unwrap();
+
+    assert_eq!(vm.stack, vec![1]);
+    assert!(vm.signal_finished);
+}
@@ -0,0 +1,39 @@
+// helpers shared by the integration tests, each test file only uses some of them
+#![allow(dead_code)]
+
+use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::assembler::assembler::{assemble, write_to_file};
+use stalfos_vm::stalfos::{VmError, VM};
+use std::path::PathBuf;
+
+// runs source after a JMP_SCAN, throwing away anything printed to the console
+pub fn run_program(vm: &mut VM, source: &str) -> Result<(), VmError> {
+    vm.console = Box::new(std::io::sink());
+    let program = parse_string(format!("JMP_SCAN\n{}", source)).unwrap().1;
+    vm.execute_program(program).map(|_| ())
+}
+
+// runs source as the start of main
+pub fn run(vm: &mut VM, source: &str) -> Result<(), VmError> {
+    run_program(vm, &format!(".main\n{}", source))
+}
+
+// runs source as the whole of main, exiting with syscall 2 after it
+pub fn run_to_exit(vm: &mut VM, source: &str) -> Result<(), VmError> {
+    run(vm, &format!("{}\nSYSCALL 2 0\n", source))
+}
+
+///
+/// Assembles the library source into a directory of its own under the temp directory. Returns
+/// the name LIBLOAD loads it by (its path without .stalib) and the directory, for the test to
+/// remove once the library has been loaded.
+///
+pub fn install_library(test: &str, source: &str) -> (String, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("stalfos_{}_{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (namespace, operations) = parse_string(source.to_string()).unwrap();
+    let path = dir.join(&namespace);
+    let name = path.to_str().unwrap().to_string();
+    write_to_file(&assemble(&operations, namespace), &format!("{}.stalib", name));
+    (name, dir)
+}
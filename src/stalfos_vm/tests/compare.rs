@@ -0,0 +1,144 @@
+mod common;
+
+use common::run;
+use stalfos_vm::stalfos::VM;
+use std::cmp::Ordering;
+
+#[test]
+fn unsigned_and_signed_compares_differ() {
+    // the top value is u32::MAX, which is -1 as a signed integer
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        PUSH 1
+        PUSH 4294967295
+        CMPu
+        BRgt unsigned_greater
+        SYSCALL 2 0
+        .unsigned_greater
+        PUSH 1
+        PUSH 4294967295
+        CMPi
+        BRlt signed_less
+        SYSCALL 2 0
+        .signed_less
+        PUSH 7
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![7]);
+    assert_eq!(vm.signal_compare, Some(Ordering::Less));
+}
+
+#[test]
+fn equal_values_take_le_and_ge_only() {
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        PUSH 5
+        PUSH 5
+        CMPu
+        BRlt wrong
+        BRgt wrong
+        BRle le
+        BR wrong
+        .le
+        BRge ge
+        BR wrong
+        .ge
+        PUSH 1
+        SYSCALL 2 0
+        .wrong
+        PUSH 0
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![1]);
+}
+
+#[test]
+fn float_compares_are_unordered_with_nan() {
+    // 2.5, 1.5 and a quiet NaN as f32 bits
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        PUSH 1075838976
+        PUSH 1069547520
+        CMPf
+        BRge wrong
+        PUSH 1069547520
+        PUSH 2143289344
+        CMPf
+        BRle wrong
+        BRge wrong
+        PUSH 1
+        SYSCALL 2 0
+        .wrong
+        PUSH 0
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![1]);
+    assert_eq!(vm.signal_compare, None);
+}
+
+#[test]
+fn taken_jmplt_opens_a_frame() {
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        PUSH 2
+        PUSH 1
+        CMPu
+        JMPlt less
+        PUSH 9
+        SYSCALL 2 0
+        .less
+        PUSH 3
+        RET
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![3, 9]);
+}
+
+#[test]
+fn compares_by_subtraction_do_not_underflow() {
+    // DJMPe falls through, DJMPne skips the PUSH 99 at 15
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        PUSH 5
+        PUSH 3
+        CMP
+        PUSH 0
+        PUSH 0
+        PUSH 5
+        PUSH 3
+        DJMPe
+        PUSH 16
+        PUSH 0
+        PUSH 5
+        PUSH 3
+        DJMPne
+        PUSH 99
+        PUSH 1
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![3u32.wrapping_sub(5), 1]);
+}
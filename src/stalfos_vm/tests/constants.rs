@@ -0,0 +1,77 @@
+mod common;
+
+use common::run;
+use stalfos_vm::stalfos::{ConstType, VM};
+
+// runs the loads for one CONST_ and returns the stack
+fn load(constant: &str, loads: &str) -> Vec<u32> {
+    let mut vm = VM::new();
+    run(&mut vm, &format!("{}\n{}\nSYSCALL 2 0\n", constant, loads)).unwrap();
+    vm.stack
+}
+
+#[test]
+fn every_const_stores_its_bits_in_one_word() {
+    let words = [
+        ("CONST_U 1 4000000000", 4_000_000_000),
+        ("CONST_F 1 -1.5", (-1.5f32).to_bits()),
+        ("CONST_F 1 infinity", f32::INFINITY.to_bits()),
+        ("CONST_I 1 -2", (-2i32) as u32),
+        ("CONST_B 1 true", 1),
+        ("CONST_B 1 false", 0),
+    ];
+
+    for (constant, word) in words {
+        assert_eq!(
+            load(constant, "LOAD_CONST 1\nLOADD 1\nGETLEN 1"),
+            vec![word, word, 1, 1],
+            "{}",
+            constant
+        );
+    }
+}
+
+#[test]
+fn const_s_is_one_word_per_4_bytes() {
+    // "hello" is padded with 3 null bytes, which GETBYTELEN leaves out
+    let stack = load("CONST_S 1 \"hello\"", "LOAD_CONST 1\nLOADD 1\nGETLEN 1\nGETBYTELEN 1");
+    let (hell, o) = (u32::from_be_bytes(*b"hell"), u32::from_be_bytes(*b"o\0\0\0"));
+    assert_eq!(stack, vec![hell, hell, o, 2, 2, 5]);
+}
+
+#[test]
+fn typed_loads_convert_between_types() {
+    let float = |f: f32| f.to_bits();
+    assert_eq!(load("CONST_F 1 -2.75", "LOADf 1\nLOADi 1"), vec![float(-2.75), (-2i32) as u32]);
+    assert_eq!(load("CONST_I 1 -3", "LOADf 1\nLOADi 1"), vec![float(-3.0), (-3i32) as u32]);
+    assert_eq!(load("CONST_U 1 7", "LOADf 1\nLOADi 1"), vec![float(7.0), 7]);
+    assert_eq!(load("CONST_B 1 true", "LOADf 1"), vec![float(1.0)]);
+    // out of range floats saturate
+    assert_eq!(load("CONST_F 1 1e20", "LOADi 1"), vec![i32::MAX as u32]);
+}
+
+#[test]
+fn reallocating_forgets_the_type() {
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        CONST_I 1 -1
+        CONST_F 2 2.5
+        ALLOC 1 1
+        SETWORD 1 0 5
+        LOADf 1
+        DEALLOC 2
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    // the ALLOC word is loaded as it is, not converted from an integer
+    assert_eq!(vm.stack, vec![5]);
+    assert!(vm.const_types.is_empty());
+
+    let mut vm = VM::new();
+    run(&mut vm, "CONST_S 3 \"s\"\nSYSCALL 2 0\n").unwrap();
+    assert_eq!(vm.const_types.get(&3), Some(&ConstType::String));
+}
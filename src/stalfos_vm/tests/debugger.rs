@@ -0,0 +1,121 @@
+use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::debugger::debugger::Debugger;
+
+// prepare runs the JMP_SCAN, so the debugger starts on .main
+// 0 JMP_SCAN, 1 .main, 2 ALLOC, 3 PUSH 1, 4 CALL, 5 PUSH 5, 6 RET, 7 .double, 8 DUP, 9 ADDu, 10 RET
+const PROGRAM: &str = "
+JMP_SCAN
+.main
+ALLOC 1 2
+PUSH 1
+CALL double
+PUSH 5
+RET
+.double
+DUP
+ADDu
+RET
+";
+
+fn debugger() -> Debugger {
+    let mut debugger = Debugger::new(parse_string(PROGRAM.to_string()).unwrap().1).unwrap();
+    debugger.vm.console = Box::new(std::io::sink());
+    debugger
+}
+
+fn command(debugger: &mut Debugger, line: &str) -> String {
+    let mut out = vec![];
+    assert!(debugger.run_command(line, &mut out).unwrap());
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn continues_to_breakpoints() {
+    let mut debugger = debugger();
+    assert_eq!(command(&mut debugger, "break double"), "breakpoint at 7\n");
+    assert_eq!(command(&mut debugger, "b 9"), "breakpoint at 9\n");
+    assert_eq!(command(&mut debugger, "b nowhere"), "unknown label 'nowhere'\n");
+    assert_eq!(command(&mut debugger, "b 99"), "pc 99 is outside of the program\n");
+
+    assert!(command(&mut debugger, "continue").starts_with("breakpoint at 7\n"));
+    assert_eq!(debugger.vm.stack, vec![1]);
+    assert!(command(&mut debugger, "c").starts_with("breakpoint at 9\n"));
+    assert_eq!(debugger.vm.stack, vec![1, 1]);
+
+    assert_eq!(command(&mut debugger, "delete 7"), "removed breakpoint at 7\n");
+    assert_eq!(command(&mut debugger, "d 7"), "no breakpoint at 7\n");
+    assert_eq!(command(&mut debugger, "c"), "program finished\n");
+    assert_eq!(debugger.vm.stack, vec![2, 5]);
+    // nothing runs once the program has finished
+    assert_eq!(command(&mut debugger, "step"), "program finished\n");
+}
+
+#[test]
+fn steps_into_calls_and_next_steps_over_them() {
+    let mut debugger = debugger();
+    assert_eq!(command(&mut debugger, "where"), "1: LABEL(\"main\")\n");
+    assert_eq!(command(&mut debugger, "step 3"), "4: CALL(\"double\")\n");
+    assert_eq!(command(&mut debugger, "s"), "7: LABEL(\"double\")\n");
+    // main's frame and the one CALL opened
+    assert_eq!(debugger.vm.stack_frame_pointers.len(), 2);
+
+    let mut debugger = self::debugger();
+    command(&mut debugger, "step 3");
+    assert_eq!(command(&mut debugger, "next"), "5: PUSH(5)\n");
+    assert_eq!(debugger.vm.stack, vec![2]);
+
+    // next stops at a breakpoint inside the call
+    let mut debugger = self::debugger();
+    command(&mut debugger, "b 9");
+    command(&mut debugger, "s 3");
+    assert_eq!(command(&mut debugger, "n"), "breakpoint at 9\n9: ADDu\n");
+    // and step stops at one before running the count out
+    let mut debugger = self::debugger();
+    command(&mut debugger, "b 3");
+    assert_eq!(command(&mut debugger, "s 10"), "breakpoint at 3\n3: PUSH(1)\n");
+}
+
+#[test]
+fn pokes_the_stack_and_memory() {
+    let mut debugger = debugger();
+    command(&mut debugger, "s 4");
+    assert_eq!(command(&mut debugger, "poke stack 0 20"), "stack[0] = 20\n");
+    assert_eq!(command(&mut debugger, "poke memory 1 0xFF"), "memory[1] = 255\n");
+    assert_eq!(command(&mut debugger, "poke stack 3 1"), "stack has no index 3\n");
+    assert_eq!(command(&mut debugger, "poke heap 0 1"), "can only poke stack or memory\n");
+    assert_eq!(command(&mut debugger, "poke stack x 1"), "invalid index or value\n");
+
+    assert!(command(&mut debugger, "p memory 1 1").starts_with("     1: 0x000000ff (255)\n"));
+    command(&mut debugger, "c");
+    assert_eq!(debugger.vm.stack, vec![40, 5]);
+}
+
+#[test]
+fn prints_memory_ranges_past_the_end() {
+    let mut debugger = debugger();
+    command(&mut debugger, "s 3");
+    let memory = debugger.vm.memory.len();
+    assert_eq!(
+        command(&mut debugger, "p memory 18446744073709551615"),
+        format!("{} words of memory\n", memory)
+    );
+    assert_eq!(
+        command(&mut debugger, "p memory 1 18446744073709551615").lines().count(),
+        memory
+    );
+}
+
+#[test]
+fn rejects_and_lists_breakpoints_outside_of_the_program() {
+    let source = "JMP_SCAN\nJMP_DEF far 99\n.JT_END\n.main\nPUSH 1\nPUSH 2\n";
+    let mut debugger = Debugger::new(parse_string(source.to_string()).unwrap().1).unwrap();
+    assert_eq!(
+        command(&mut debugger, "b far"),
+        "label 'far' points at pc 99, outside of the program\n"
+    );
+
+    // the program can be swapped out from under a breakpoint through the public vm
+    assert_eq!(command(&mut debugger, "b 5"), "breakpoint at 5\n");
+    debugger.vm.program.truncate(5);
+    assert_eq!(command(&mut debugger, "breakpoints"), "5: <out of range>\n");
+}
@@ -0,0 +1,208 @@
+use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::assembler::assembler::{assemble, try_parse_binary};
+use stalfos_vm::disassembler::disassembler::disassemble;
+
+// source -> binary -> source -> binary
+fn round_trip(source: &str) -> (Vec<u8>, Vec<u8>, String) {
+    let (ns, ops) = parse_string(source.to_string()).unwrap();
+    let binary = assemble(&ops, ns);
+
+    let (decoded, decoded_ns) = try_parse_binary(binary.clone()).unwrap();
+    let disassembled = disassemble(&decoded, &decoded_ns);
+
+    let (new_ns, new_ops) = parse_string(disassembled.clone()).unwrap();
+    (binary, assemble(&new_ops, new_ns), disassembled)
+}
+
+#[test]
+fn round_trips_every_operator() {
+    let source = r#"
+JMP_DEF "main" 3
+JMP_DEF "odd label" 40
+JMP_SCAN
+.main
+PUSH 0xFF
+LOAD 1
+LOADD 1
+CONST_U 1 4294967295
+CONST_F 2 -1.5
+CONST_F 3 0.1
+CONST_F 4 infinity
+CONST_I 5 -2147483648
+CONST_B 6 true
+CONST_S 7 "quote \" backslash \\ newline \n return \r tab \t null \0 ; not a comment"
+CONST_S 8 ""
+LOAD_CONST 7
+POP
+ALLOC 9 4
+DEALLOC 9
+ALLOC_PERSIST 9 2
+POPS 9
+GETLEN 7
+GETBYTELEN 7
+GETBYTE 7 1
+GETWORD 7 0
+SETBYTE 7 1 255
+SETWORD 7 0 12
+DUP
+DUPO 2
+SWAP
+ADDu ADDi
+"#;
+    // ADDu ADDi on one line is an error, make sure that is not what is being tested
+    assert!(parse_string(source.to_string()).is_err());
+
+    let source = source.replace("ADDu ADDi", "ADDu\nADDi\nADDfi\nADDif\nADDf\nSUBu\nSUBi\nSUBfi\nSUBif\nSUBf\nMULu\nMULi\nMULfi\nMULif\nMULf\nDIVu\nDIVi\nDIVfi\nDIVif\nDIVf\nMODu\nMODi\nMODfi\nMODif\nMODf\nROR\nROL\nLSR\nASR\nLSL\nASL\nNEG\nAND\nXOR\nOR\nNOR\nNAND\nCNT\nCMP")
+        + r#"
+LABEL "odd label"
+JMP "main"
+JMPo "main"
+JMPe "main"
+JMPne "main"
+JMPs "main" "odd label"
+LABEL "dotted.label"
+LABEL "a\"b"
+SYSCALL 1 1
+SYSCALLD 3
+EXCEPT_THROW
+EXCEPT_CATCH "main"
+RET
+EMIT
+EMITS 7
+EMITW 7
+EMITD 7
+DJMP
+DJMPe
+DJMPne
+DALLOC 2
+DNEW
+DGETSIZE
+DLOADVALUE
+DDEALLOC
+DGETWORD
+DSETWORD
+DGETBYTE
+DSETBYTE
+LIBLOAD "lib"
+DLIBLOAD
+LIBCALL "lib" "func"
+DLIBCALL "func"
+LIBDCALL "lib"
+DLIBDCALL
+CALL "main"
+CALLD
+BR "main"
+BRo "main"
+BRe "main"
+BRne "main"
+BRs "main" "odd label"
+DBR
+DBRe
+DBRne
+EXCEPT_THROWC 404
+EXCEPT_THROWD
+EXCEPT_THROWP 7 9
+EXCEPT_CATCH_RANGE "main" 400 499
+EXCEPT_FINALLY "main"
+EXCEPT_END
+EXCEPT_CODE
+EXCEPT_PAYLOAD 9
+CMPu
+CMPi
+CMPf
+JMPlt "main"
+JMPgt "main"
+JMPle "main"
+JMPge "main"
+BRlt "main"
+BRgt "main"
+BRle "main"
+BRge "main"
+SETREG64 1
+GETREG64 1
+SETREG128 1
+GETREG128 1
+ADD64u
+ADD64i
+SUB64u
+SUB64i
+MUL64u
+MUL64i
+DIV64u
+DIV64i
+LSL64
+LSR64
+ASR64
+CMP64u
+CMP64i
+ADD128u
+SUB128u
+MUL128u
+DIV128u
+LSL128
+LSR128
+CMP128u
+LOADf 2
+LOADi 5
+ITOF
+FTOI
+UTOF
+SQRTf
+ABSf
+FLOORf
+CEILf
+MINf
+MAXf
+POWf
+RORC 3
+ROLC 3
+LSRC 3
+ASRC 3
+LSLC 3
+ASLC 3
+RORD
+ROLD
+LSRD
+ASRD
+LSLD
+ASLD
+CLZ
+CTZ
+OVER
+ROT
+PICK 2
+ROLL 2
+DROP 2
+DEPTH
+CLEAR
+ENTER 2
+LOCAL_GET 1
+LOCAL_SET 1
+"#;
+
+    let (binary, new_binary, disassembled) = round_trip(&source);
+    assert_eq!(binary, new_binary, "{}", disassembled);
+    assert!(disassembled.contains(".main\n"));
+    assert!(disassembled.contains("LABEL \"odd label\"\n"));
+    assert!(disassembled.contains("LABEL \"a\\\"b\"\n"));
+    assert!(disassembled.contains("JMP_DEF \"main\" 3\n"));
+}
+
+#[test]
+fn round_trips_library_namespace() {
+    let source = "#<dyn_helloworld>\nJMP_SCAN\n.hello\nCONST_S 1 \"hello, world!\"\nLOADD 1\nSYSCALLD 3\nRET\n";
+    let (binary, new_binary, disassembled) = round_trip(source);
+    assert_eq!(binary, new_binary);
+    assert!(disassembled.starts_with("#<dyn_helloworld>\n"));
+}
+
+#[test]
+fn escapes_strings() {
+    let source = "JMP_SCAN\n.main\nCONST_S 1 \"a\\\"b\\\\c\\nd\"\nRET\n";
+    let (ns, ops) = parse_string(source.to_string()).unwrap();
+    match &ops[2] {
+        stalfos_vm::ops::ops::Operator::CONST_S(_, s) => assert_eq!(s, "a\"b\\c\nd"),
+        op => panic!("unexpected {:?}", op),
+    }
+    assert!(disassemble(&ops, &ns).contains("CONST_S 1 \"a\\\"b\\\\c\\nd\""));
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::run;
+use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::ops::ops::Operator;
+use stalfos_vm::stalfos::{VmError, VM};
+
+// runs source as main on a new VM, the first operation of source is at pc 2
+fn error_of(source: &str) -> VmError {
+    run(&mut VM::new(), source).unwrap_err()
+}
+
+#[test]
+fn popping_an_empty_stack_underflows() {
+    let error = error_of("PUSH 1\nPOP\nPOP\n");
+    assert!(matches!(error, VmError::StackUnderflow { pc: 4, op } if matches!(*op, Operator::POP)));
+}
+
+#[test]
+fn jumping_to_a_missing_label_fails() {
+    match error_of("JMP nowhere\n") {
+        VmError::UnknownLabel { pc: 2, op, label } => {
+            assert!(matches!(*op, Operator::JMP(ref target) if target == "nowhere"));
+            assert_eq!(label, "nowhere");
+        }
+        other => panic!("expected UnknownLabel at 2, got {:?}", other),
+    }
+}
+
+#[test]
+fn loading_an_unknown_allocation_fails() {
+    let error = error_of("ALLOC 1 1\nLOAD 2\n");
+    assert!(matches!(
+        error,
+        VmError::UnknownAllocation { pc: 3, op, ptr: 2 } if matches!(*op, Operator::LOAD(2))
+    ));
+}
+
+#[test]
+fn reading_past_an_allocation_is_out_of_bounds() {
+    let error = error_of("CONST_U 1 7\nGETBYTE 1 4\n");
+    assert!(matches!(
+        error,
+        VmError::OutOfBounds { pc: 3, op, index: 4, len: 4 } if matches!(*op, Operator::GETBYTE(1, 4))
+    ));
+}
+
+#[test]
+fn dividing_by_zero_fails() {
+    let error = error_of("PUSH 0\nPUSH 5\nDIVu\n");
+    assert!(matches!(error, VmError::DivideByZero { pc: 4, op } if matches!(*op, Operator::DIVu)));
+}
+
+#[test]
+fn running_off_the_end_of_the_program_fails() {
+    assert!(matches!(error_of("PUSH 1\n"), VmError::InvalidProgramCounter { pc: 3 }));
+}
+
+#[test]
+fn programs_without_main_cannot_be_prepared() {
+    let program = parse_string("JMP_SCAN\nPUSH 1\n".to_string()).unwrap().1;
+    assert!(matches!(VM::new().add_ops(program).prepare(), Err(VmError::NoMain)));
+}
+
+#[test]
+fn errors_leave_the_vm_for_the_host_to_look_at() {
+    let mut vm = VM::new();
+    let result = run(&mut vm, "PUSH 1\nPUSH 2\nPOP\nJMP nowhere\n");
+
+    assert!(matches!(result, Err(VmError::UnknownLabel { pc: 5, .. })));
+    assert_eq!(vm.program_counter, 5);
+    assert_eq!(vm.stack, vec![1]);
+}
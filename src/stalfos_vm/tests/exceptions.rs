@@ -0,0 +1,175 @@
+mod common;
+
+use common::run_program;
+use stalfos_vm::stalfos::{VmError, VM};
+
+#[test]
+fn catch_ranges_skip_other_codes() {
+    let mut vm = VM::new();
+    run_program(
+        &mut vm,
+        "
+        .main
+        EXCEPT_CATCH everything
+        CALL middle
+        RET
+        .everything
+        PUSH 1
+        EXCEPT_CODE
+        SYSCALL 2 0
+        .middle
+        EXCEPT_CATCH_RANGE small 0 9
+        EXCEPT_THROWC 404
+        .small
+        PUSH 2
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![1, 404]);
+    // the catch runs in main, the frame of middle is gone
+    assert_eq!(vm.stack_frame_pointers.len(), 1);
+}
+
+#[test]
+fn finally_runs_before_the_outer_catch() {
+    let mut vm = VM::new();
+    run_program(
+        &mut vm,
+        "
+        .main
+        EXCEPT_CATCH_RANGE not_found 404 404
+        CALL fetch
+        RET
+        .not_found
+        EXCEPT_CODE
+        RET
+        .fetch
+        EXCEPT_FINALLY cleanup
+        EXCEPT_THROWC 404
+        .cleanup
+        PUSH 1
+        EXCEPT_END
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![1, 404]);
+    assert!(vm.signal_finished);
+}
+
+#[test]
+fn a_throw_in_a_handler_goes_to_the_handlers_before_it() {
+    let mut vm = VM::new();
+    run_program(
+        &mut vm,
+        "
+        .main
+        EXCEPT_CATCH outer
+        CALL thrower
+        RET
+        .outer
+        EXCEPT_CODE
+        SYSCALL 2 0
+        .thrower
+        EXCEPT_CATCH inner
+        EXCEPT_THROWC 1
+        .inner
+        EXCEPT_CODE
+        EXCEPT_THROWC 2
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![1, 2]);
+}
+
+#[test]
+fn a_catch_applies_again_after_except_end() {
+    let mut vm = VM::new();
+    run_program(
+        &mut vm,
+        "
+        .main
+        PUSH 0
+        EXCEPT_CATCH again
+        .try
+        DUP
+        PUSH 3
+        CMP
+        BRe done
+        EXCEPT_THROW
+        .again
+        PUSH 1
+        ADDu
+        EXCEPT_END
+        BR try
+        .done
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![3]);
+    assert!(vm.exception.is_none());
+}
+
+#[test]
+fn payload_survives_unwinding() {
+    let mut vm = VM::new();
+    run_program(
+        &mut vm,
+        "
+        .main
+        EXCEPT_CATCH handler
+        CALL thrower
+        RET
+        .handler
+        EXCEPT_PAYLOAD 3
+        LOADD 3
+        SYSCALL 2 0
+        .thrower
+        ALLOC 1 4
+        CONST_S 2 \"oops\"
+        EXCEPT_THROWP 9 2
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![u32::from_be_bytes(*b"oops"), 1]);
+    // 1 was released with the frame of thrower, the payload is owned by main now
+    let identifiers: Vec<usize> = vm.static_alloc_table.keys().copied().collect();
+    assert_eq!(identifiers, vec![3]);
+    assert_eq!(vm.stack_frame_pointers[0].allocations, vec![3]);
+}
+
+#[test]
+fn uncaught_exceptions_report_code_and_pc() {
+    let mut vm = VM::new();
+    let result = run_program(
+        &mut vm,
+        "
+        .main
+        EXCEPT_CATCH_RANGE handler 1 6
+        CALL thrower
+        .handler
+        RET
+        .thrower
+        PUSH 7
+        EXCEPT_THROWD
+        ",
+    );
+
+    assert!(matches!(result, Err(VmError::Uncaught { code: 7, pc: 8 })));
+    assert_eq!(vm.exception.unwrap().code, 7);
+    assert!(vm.stack_frame_pointers.is_empty());
+}
+
+#[test]
+fn handlers_are_resolved_in_prepare() {
+    let mut vm = VM::new();
+    let result = run_program(&mut vm, ".main\nEXCEPT_FINALLY missing\nRET\n");
+
+    assert!(matches!(result, Err(VmError::UnknownLabel { pc: 2, .. })));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::run_to_exit;
+use stalfos_vm::stalfos::VM;
+
+fn floats(stack: &[u32]) -> Vec<f32> {
+    stack.iter().map(|bits| f32::from_bits(*bits)).collect()
+}
+
+#[test]
+fn converts_between_integers_and_floats() {
+    let mut vm = VM::new();
+    run_to_exit(
+        &mut vm,
+        "
+        PUSH 4294967294
+        ITOF
+        PUSH 4294967294
+        UTOF
+        CONST_F 1 -7.9
+        LOAD_CONST 1
+        FTOI
+        CONST_F 2 3e10
+        LOAD_CONST 2
+        FTOI
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack[0], (-2.0f32).to_bits());
+    assert_eq!(vm.stack[1], (4294967294u32 as f32).to_bits());
+    assert_eq!(vm.stack[2], (-7i32) as u32);
+    assert_eq!(vm.stack[3], i32::MAX as u32);
+}
+
+#[test]
+fn single_value_math() {
+    let mut vm = VM::new();
+    run_to_exit(
+        &mut vm,
+        "
+        CONST_F 1 -2.5
+        CONST_F 2 16
+        LOAD_CONST 2
+        SQRTf
+        LOAD_CONST 1
+        ABSf
+        LOAD_CONST 1
+        FLOORf
+        LOAD_CONST 1
+        CEILf
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(floats(&vm.stack), vec![4.0, 2.5, -3.0, -2.0]);
+}
+
+#[test]
+fn two_value_math_uses_the_top_value_first() {
+    let mut vm = VM::new();
+    run_to_exit(
+        &mut vm,
+        "
+        CONST_F 1 3
+        CONST_F 2 2
+        LOAD_CONST 1
+        LOAD_CONST 2
+        POWf
+        LOAD_CONST 1
+        LOAD_CONST 2
+        MINf
+        LOAD_CONST 1
+        LOAD_CONST 2
+        MAXf
+        ",
+    )
+    .unwrap();
+
+    // 2 ^ 3
+    assert_eq!(floats(&vm.stack), vec![8.0, 2.0, 3.0]);
+}
+
+#[test]
+fn sqrt_of_a_negative_is_nan() {
+    let mut vm = VM::new();
+    run_to_exit(&mut vm, "CONST_F 1 -1\nLOAD_CONST 1\nSQRTf\nDUP\nDUP\nCMPf").unwrap();
+
+    assert!(f32::from_bits(vm.stack[0]).is_nan());
+    assert_eq!(vm.signal_compare, None);
+}
@@ -0,0 +1,89 @@
+use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::stalfos::ops::Operator;
+use stalfos_vm::stalfos::VM;
+
+fn prepare(source: &str) -> VM {
+    let mut vm = VM::new();
+    vm.console = Box::new(std::io::sink());
+    vm.add_ops(parse_string(source.to_string()).unwrap().1)
+        .prepare()
+        .unwrap();
+    vm
+}
+
+// steps until the first RET has run
+fn run_past_ret(vm: &mut VM) {
+    while !matches!(vm.program[vm.program_counter], Operator::RET) {
+        vm.step(&mut Default::default()).unwrap();
+    }
+    vm.step(&mut Default::default()).unwrap();
+}
+
+#[test]
+fn ret_releases_the_frames_allocations() {
+    let mut vm = prepare(
+        "JMP_SCAN
+        .main
+        ALLOC 1 2
+        JMP f
+        .f
+        ALLOC 2 4
+        CONST_U 3 7
+        ALLOC_PERSIST 4 3
+        RET
+        ",
+    );
+    run_past_ret(&mut vm);
+
+    let identifiers: Vec<usize> = vm.static_alloc_table.keys().copied().collect();
+    assert_eq!(identifiers, vec![1, 4]);
+    assert_eq!(vm.allocator.allocations(), vec![(0, 2), (7, 3)]);
+    assert_eq!(vm.stack_frame_pointers.len(), 1);
+}
+
+#[test]
+fn persisting_a_reused_identifier_survives_ret() {
+    // the persistent allocation lands at the location the scoped one was freed from
+    let mut vm = prepare(
+        "JMP_SCAN
+        .main
+        JMP f
+        .f
+        ALLOC 1 4
+        ALLOC_PERSIST 1 4
+        ALLOC 2 1
+        DEALLOC 2
+        ALLOC_PERSIST 2 1
+        RET
+        ",
+    );
+    run_past_ret(&mut vm);
+
+    assert_eq!(vm.static_alloc_table.get(&1), Some(&(0, 4)));
+    assert_eq!(vm.static_alloc_table.get(&2), Some(&(4, 1)));
+}
+
+#[test]
+fn unwinding_releases_each_frame() {
+    let mut vm = prepare(
+        "JMP_SCAN
+        .main
+        EXCEPT_CATCH handler
+        ALLOC_PERSIST 1 1
+        JMP f
+        .f
+        ALLOC 2 3
+        JMP g
+        .g
+        ALLOC 3 3
+        EXCEPT_THROW
+        .handler
+        SYSCALL 2 0
+        ",
+    );
+    vm.run().unwrap();
+
+    let identifiers: Vec<usize> = vm.static_alloc_table.keys().copied().collect();
+    assert_eq!(identifiers, vec![1]);
+    assert_eq!(vm.allocator.allocations(), vec![(0, 1)]);
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::install_library;
+use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::ops::ops::Operator;
+use stalfos_vm::stalfos::{RunOutcome, VmError, VM};
+use std::path::PathBuf;
+
+fn prepared(source: &str) -> VM {
+    let mut vm = VM::new();
+    vm.add_ops(parse_string(source.to_string()).unwrap().1)
+        .prepare()
+        .unwrap();
+    vm
+}
+
+// installs library (see install_library) and returns a VM that loads it and calls function
+fn calling_library(test: &str, library: &str, function: &str) -> (VM, PathBuf) {
+    let (name, dir) = install_library(&format!("fuel_{}", test), library);
+    let source = format!(
+        "JMP_SCAN\n.main\nLIBLOAD {:?}\nLIBCALL {:?} {}\nSYSCALL 2 0\n",
+        name, name, function
+    );
+    let mut vm = prepared(&source);
+    vm.console = Box::new(std::io::sink());
+    (vm, dir)
+}
+
+#[test]
+fn infinite_loop_runs_out_of_fuel() {
+    let mut vm = prepared("JMP_SCAN\n.main\n.loop\nPUSH 1\nPOP\nJMP loop\n");
+
+    assert!(matches!(vm.run_for(1000), RunOutcome::OutOfFuel));
+    assert!(matches!(vm.run_for(1000), RunOutcome::OutOfFuel));
+}
+
+#[test]
+fn resumes_where_it_stopped() {
+    let mut vm = prepared("JMP_SCAN\n.main\nPUSH 1\nPUSH 2\nPUSH 3\nSYSCALL 2 0\n");
+    vm.console = Box::new(std::io::sink());
+
+    // LABEL, PUSH 1, PUSH 2
+    assert!(matches!(vm.run_for(3), RunOutcome::OutOfFuel));
+    assert_eq!(vm.stack, vec![1, 2]);
+
+    assert!(matches!(vm.run_for(0), RunOutcome::OutOfFuel));
+    assert_eq!(vm.stack, vec![1, 2]);
+
+    assert!(matches!(vm.run_for(10), RunOutcome::Finished));
+    assert_eq!(vm.stack, vec![1, 2, 3]);
+    assert!(matches!(vm.run_for(10), RunOutcome::Finished));
+}
+
+#[test]
+fn uses_configured_costs() {
+    let mut vm = prepared("JMP_SCAN\n.main\nPUSH 1\nPUSH 2\nADDu\nSYSCALL 2 0\n");
+    vm.console = Box::new(std::io::sink());
+    vm.fuel_costs.set_cost(&Operator::PUSH(0), 5).set_cost(&Operator::LABEL(String::new()), 0);
+
+    assert!(matches!(vm.run_for(9), RunOutcome::OutOfFuel));
+    assert_eq!(vm.stack, vec![1]);
+
+    // PUSH 2 (5) and ADDu (1), leaving nothing for the SYSCALL
+    assert!(matches!(vm.run_for(6), RunOutcome::OutOfFuel));
+    assert_eq!(vm.stack, vec![3]);
+    assert!(matches!(vm.run_for(1), RunOutcome::Finished));
+}
+
+#[test]
+fn traps_on_faults() {
+    let mut vm = prepared("JMP_SCAN\n.main\nPOP\n");
+
+    match vm.run_for(100) {
+        RunOutcome::Trapped(error) => assert_eq!(error.to_string(), "stack underflow at 2 (POP)"),
+        outcome => panic!("unexpected {:?}", outcome),
+    }
+}
+
+#[test]
+fn library_loops_run_out_of_fuel() {
+    let library = "#<plib>\nJMP_DEF spin 2\n.JT_END\n.spin\nBR spin\n";
+    let (mut vm, dir) = calling_library("spin", library, "spin");
+
+    let outcome = vm.run_for(1000);
+    std::fs::remove_dir_all(&dir).unwrap();
+    match outcome {
+        RunOutcome::Trapped(VmError::LibraryFault { pc, error, .. }) => {
+            assert_eq!(pc, 3);
+            assert!(matches!(*error, VmError::OutOfFuel { .. }), "{}", error);
+        }
+        outcome => panic!("unexpected {:?}", outcome),
+    }
+}
+
+#[test]
+fn library_fuel_is_charged_to_the_caller() {
+    // seven runs LABEL, PUSH 7, PUSH 1 and RET
+    let library = "#<plib>\nJMP_DEF seven 2\n.JT_END\n.seven\nPUSH 7\nPUSH 1\nRET\n";
+
+    // LABEL, LIBLOAD, LIBCALL and the library's 4, leaving nothing for the SYSCALL
+    let (mut vm, dir) = calling_library("charged", library, "seven");
+    let (mut short_vm, _) = calling_library("charged", library, "seven");
+    let outcomes = (vm.run_for(7), short_vm.run_for(6));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(matches!(outcomes.0, RunOutcome::OutOfFuel));
+    assert_eq!(vm.stack, vec![7, 1]);
+    assert!(matches!(vm.run_for(1), RunOutcome::Finished));
+    assert!(matches!(outcomes.1, RunOutcome::Trapped(_)));
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use common::run;
+use stalfos_vm::stalfos::{VmError, VM};
+
+fn run_new(source: &str) -> (VM, Result<(), VmError>) {
+    let mut vm = VM::new();
+    let result = run(&mut vm, source);
+    (vm, result)
+}
+
+#[test]
+fn reads_and_writes_through_a_pointer() {
+    let (vm, result) = run_new("
+        PUSH 3
+        DNEW
+        ; word 1 = 42
+        PUSH 42
+        PUSH 1
+        DUPO 4
+        DUPO 4
+        DSETWORD
+        ; byte 3 (the low byte of word 0) = 0xAB
+        PUSH 0xAB
+        PUSH 3
+        DUPO 4
+        DUPO 4
+        DSETBYTE
+        PUSH 1
+        DUPO 3
+        DUPO 3
+        DGETWORD
+        PUSH 3
+        DUPO 4
+        DUPO 4
+        DGETBYTE
+        DUPO 4
+        DUPO 4
+        DGETSIZE
+        DUPO 5
+        DUPO 5
+        DLOADVALUE
+        SYSCALL 2 0
+    ");
+
+    result.unwrap();
+    let (pointer, values) = vm.stack.split_at(2);
+    assert_eq!(values, &[42, 0xAB, 3, 0xAB, 42, 0, 3]);
+    assert_eq!(pointer, &[vm.dynamic_allocations.keys().next().copied().unwrap() as u32, 0]);
+}
+
+#[test]
+fn pointers_can_be_stored_in_the_heap() {
+    // node b holds the pointer to node a, read a's value back through it
+    let (vm, result) = run_new("
+        ALLOC 1 5
+        PUSH 1
+        DNEW
+        PUSH 7
+        PUSH 0
+        DUPO 4
+        DUPO 4
+        DSETWORD
+        PUSH 2
+        DNEW
+        ; b[0] = a low, b[1] = a high
+        DUPO 4
+        PUSH 0
+        DUPO 4
+        DUPO 4
+        DSETWORD
+        DUPO 3
+        PUSH 1
+        DUPO 4
+        DUPO 4
+        DSETWORD
+        ; load a through b
+        PUSH 0
+        DUPO 3
+        DUPO 3
+        DGETWORD
+        PUSH 1
+        DUPO 4
+        DUPO 4
+        DGETWORD
+        PUSH 0
+        DUPO 3
+        DUPO 3
+        DGETWORD
+        SYSCALL 2 0
+    ");
+
+    result.unwrap();
+    assert_eq!(vm.stack.last(), Some(&7));
+    // a is not at location 0, so the pointer read back really was used
+    assert_eq!(vm.stack[0], 5);
+}
+
+#[test]
+fn freed_pointers_are_invalid() {
+    let (vm, result) = run_new("
+        PUSH 2
+        DNEW
+        DUPO 2
+        DUPO 2
+        DDEALLOC
+        DGETSIZE
+    ");
+
+    assert!(matches!(result, Err(VmError::InvalidPointer { pc: 7, .. })));
+    assert!(vm.dynamic_allocations.is_empty());
+    assert!(vm.allocator.allocations().is_empty());
+}
+
+#[test]
+fn indexes_are_bounds_checked() {
+    let (_, result) = run_new("
+        PUSH 9
+        PUSH 2
+        PUSH 2
+        DNEW
+        DSETWORD
+    ");
+    assert!(matches!(result, Err(VmError::OutOfBounds { index: 2, len: 2, .. })));
+
+    let (_, result) = run_new("
+        PUSH 0
+        PUSH 0
+        DNEW
+        DGETBYTE
+    ");
+    assert!(matches!(result, Err(VmError::OutOfBounds { index: 0, len: 0, .. })));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{install_library, run};
+use stalfos_vm::stalfos::{VmError, VM};
+
+#[test]
+fn dalloc_of_a_huge_size_traps() {
+    let mut vm = VM::new();
+    let result = run(&mut vm, "PUSH 0xFFFFFFFF\nDALLOC 1\n");
+
+    assert!(matches!(result, Err(VmError::MemoryLimit { requested: 0xFFFFFFFF, .. })));
+    assert_eq!(vm.memory.len(), 0);
+}
+
+#[test]
+fn memory_limit_applies_to_every_allocation() {
+    let mut vm = VM::new();
+    vm.limits.max_memory_words = 4;
+    let result = run(&mut vm, "ALLOC 1 3\nCONST_S 2 \"abcd\"\nCONST_U 3 1\n");
+
+    assert!(matches!(result, Err(VmError::MemoryLimit { pc: 4, .. })));
+    assert_eq!(vm.memory.len(), 4);
+}
+
+#[test]
+fn stack_depth_traps() {
+    let mut vm = VM::new();
+    vm.limits.max_stack_depth = 2;
+    let result = run(&mut vm, "PUSH 1\nPUSH 2\nPUSH 3\n");
+
+    assert!(matches!(result, Err(VmError::StackOverflow { pc: 4, limit: 2, .. })));
+}
+
+#[test]
+fn frame_depth_traps() {
+    let mut vm = VM::new();
+    vm.limits.max_frame_depth = 10;
+    let result = run(&mut vm, ".recurse\nJMP recurse\n");
+
+    assert!(matches!(result, Err(VmError::FrameOverflow { limit: 10, .. })));
+}
+
+// main takes 4 words and calls outer, which takes 3 and calls function in an inner library
+fn nested_library_call(function: &str) -> Result<(), VmError> {
+    let inner = "#<ilib>\nJMP_DEF fits 3\nJMP_DEF too_big 7\n.JT_END\n\
+        .fits\nALLOC 1 3\nPUSH 0\nRET\n.too_big\nALLOC 1 4\nPUSH 0\nRET\n";
+    let (inner, inner_dir) = install_library(&format!("limits_inner_{}", function), inner);
+    let outer = format!(
+        "#<olib>\nJMP_DEF outer 2\n.JT_END\n\
+        .outer\nALLOC 1 3\nLIBLOAD {:?}\nLIBCALL {:?} {}\nPUSH 0\nRET\n",
+        inner, inner, function
+    );
+    let (outer, outer_dir) = install_library(&format!("limits_outer_{}", function), &outer);
+
+    let mut vm = VM::new();
+    vm.limits.max_memory_words = 10;
+    let source = format!("ALLOC 1 4\nLIBLOAD {:?}\nLIBCALL {:?} outer\nSYSCALL 2 0\n", outer, outer);
+    let result = run(&mut vm, &source);
+    std::fs::remove_dir_all(&inner_dir).unwrap();
+    std::fs::remove_dir_all(&outer_dir).unwrap();
+    result
+}
+
+#[test]
+fn nested_library_calls_share_the_memory_limit() {
+    nested_library_call("fits").unwrap();
+
+    // the inner library only has the 3 words main and outer left
+    match nested_library_call("too_big") {
+        Err(VmError::LibraryFault { error, .. }) => match *error {
+            VmError::LibraryFault { error, .. } => {
+                assert!(matches!(*error, VmError::MemoryLimit { requested: 4, limit: 3, .. }))
+            }
+            other => panic!("expected the inner library to fault, got {:?}", other),
+        },
+        other => panic!("expected a LibraryFault, got {:?}", other),
+    }
+}
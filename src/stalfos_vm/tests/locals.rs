@@ -0,0 +1,153 @@
+mod common;
+
+use common::{install_library, run};
+use stalfos_vm::stalfos::{VmError, VM};
+
+#[test]
+fn recursive_calls_have_their_own_locals() {
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        PUSH 6
+        CALL factorial
+        SYSCALL 2 0
+        .factorial
+        ENTER 1
+        LOCAL_SET 0
+        PUSH 1
+        LOCAL_GET 0
+        CMPu
+        BRle one
+        PUSH 1
+        LOCAL_GET 0
+        SUBu
+        CALL factorial
+        LOCAL_GET 0
+        MULu
+        RET
+        .one
+        PUSH 1
+        RET
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![720]);
+    assert_eq!(vm.stack_frame_pointers.len(), 1);
+}
+
+#[test]
+fn callers_locals_survive_calls_and_unwinding() {
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        ENTER 2
+        PUSH 5
+        LOCAL_SET 1
+        EXCEPT_CATCH caught
+        CALL clobber
+        .caught
+        EXCEPT_END
+        LOCAL_GET 0
+        LOCAL_GET 1
+        SYSCALL 2 0
+        .clobber
+        ENTER 3
+        PUSH 9
+        LOCAL_SET 1
+        EXCEPT_THROWC 1
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![0, 5]);
+    assert_eq!(vm.stack_frame_pointers.len(), 1);
+    assert_eq!(vm.stack_frame_pointers[0].locals, vec![0, 5]);
+}
+
+#[test]
+fn enter_resizes_the_locals() {
+    let mut vm = VM::new();
+    run(&mut vm, "ENTER 2\nPUSH 7\nLOCAL_SET 0\nPUSH 8\nLOCAL_SET 1\nENTER 1\nENTER 3\nSYSCALL 2 0\n").unwrap();
+
+    assert_eq!(vm.stack_frame_pointers[0].locals, vec![7, 0, 0]);
+}
+
+#[test]
+fn bad_local_access() {
+    let result = run(&mut VM::new(), "LOCAL_GET 0\n");
+    assert!(matches!(result, Err(VmError::OutOfBounds { pc: 2, index: 0, len: 0, .. })));
+
+    let mut vm = VM::new();
+    let result = run(&mut vm, "ENTER 1\nPUSH 3\nLOCAL_SET 1\n");
+    assert!(matches!(result, Err(VmError::OutOfBounds { pc: 4, index: 1, len: 1, .. })));
+    assert_eq!(vm.stack, vec![3]);
+
+    let mut vm = VM::new();
+    vm.limits.max_memory_words = 16;
+    let result = run(&mut vm, "ENTER 17\n");
+    assert!(matches!(result, Err(VmError::MemoryLimit { requested: 17, limit: 16, .. })));
+}
+
+#[test]
+fn locals_of_every_frame_count_against_the_memory_limit() {
+    // each call holds 4 slots until it returns, so the 5th nested call goes over
+    let source = "
+        PUSH 5
+        CALL nest
+        SYSCALL 2 0
+        .nest
+        ENTER 4
+        PUSH 1
+        SWAP
+        SUBu
+        DUP
+        BRne deeper
+        RET
+        .deeper
+        CALL nest
+        RET
+        ";
+    let mut vm = VM::new();
+    vm.limits.max_memory_words = 19;
+    let result = run(&mut vm, source);
+    assert!(matches!(result, Err(VmError::MemoryLimit { requested: 4, limit: 19, .. })));
+
+    let mut vm = VM::new();
+    vm.limits.max_memory_words = 20;
+    run(&mut vm, source).unwrap();
+
+    // and the slots are given back as the frames close, so memory can use them afterwards
+    let mut vm = VM::new();
+    vm.limits.max_memory_words = 20;
+    run(&mut vm, &format!("{}\n", source.replace("SYSCALL 2 0", "ALLOC 1 20\nSYSCALL 2 0"))).unwrap();
+}
+
+#[test]
+fn memory_and_locals_share_the_memory_limit() {
+    let mut vm = VM::new();
+    vm.limits.max_memory_words = 8;
+    let result = run(&mut vm, "ALLOC 1 6\nENTER 2\nENTER 3\n");
+    assert!(matches!(result, Err(VmError::MemoryLimit { pc: 4, requested: 3, limit: 8, .. })));
+
+    let mut vm = VM::new();
+    vm.limits.max_memory_words = 8;
+    let result = run(&mut vm, "ENTER 6\nALLOC 1 2\nALLOC 2 1\n");
+    assert!(matches!(result, Err(VmError::MemoryLimit { pc: 4, requested: 1, limit: 8, .. })));
+}
+
+#[test]
+fn library_functions_have_locals() {
+    let library = "#<plib>\nJMP_DEF twice 2\n.JT_END\n.twice\nENTER 1\nLOCAL_SET 0\nLOCAL_GET 0\nLOCAL_GET 0\nADDu\nPUSH 1\nRET\n";
+    let (name, dir) = install_library("locals", library);
+
+    let mut vm = VM::new();
+    let result = run(&mut vm, &format!("PUSH 21\nLIBLOAD {:?}\nLIBCALL {:?} twice\nSYSCALL 2 0\n", name, name));
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+
+    // the library gets a copy of the stack, and its RET hands back one value
+    assert_eq!(vm.stack, vec![21, 42, 1]);
+}
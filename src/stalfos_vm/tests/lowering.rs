@@ -0,0 +1,78 @@
+mod common;
+
+use common::run_program;
+use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::ops::ops::Operator;
+use stalfos_vm::stalfos::{VmError, VM};
+
+fn prepared(source: &str) -> VM {
+    let mut vm = VM::new();
+    vm.console = Box::new(std::io::sink());
+    vm.add_ops(parse_string(source.to_string()).unwrap().1).prepare().unwrap();
+    vm
+}
+
+#[test]
+fn unknown_labels_only_fail_when_the_jump_is_taken() {
+    let mut vm = VM::new();
+    // 0 JMP_SCAN, 1 .main, 2 PUSH 1, 3 JMPe, 4 PUSH 0, 5 JMPs, 6 .there, 7 PUSH 0, 8 JMPe
+    let result = run_program(
+        &mut vm,
+        "
+        .main
+        PUSH 1
+        JMPe nowhere
+        PUSH 0
+        JMPs there nowhere
+        .there
+        PUSH 0
+        JMPe nowhere
+        ",
+    );
+    match result {
+        Err(VmError::UnknownLabel { pc: 8, label, .. }) => assert_eq!(label, "nowhere"),
+        other => panic!("expected UnknownLabel at 8, got {:?}", other),
+    }
+}
+
+#[test]
+fn ops_added_after_prepare_are_lowered_before_they_run() {
+    let mut vm = prepared("JMP_SCAN\nBR main\n.exit\nSYSCALL 2 0\n.main\nPUSH 1\n");
+    // falls off the end of main into the added ops, which jump back to exit
+    vm.add_ops(parse_string("PUSH 2\nBR exit\n".to_string()).unwrap().1);
+    vm.run().unwrap();
+
+    assert!(vm.signal_finished);
+    assert_eq!(vm.stack, vec![1, 2]);
+}
+
+#[test]
+fn step_jumps_to_the_lowered_targets() {
+    // 0 JMP_SCAN, 1 .main, 2 BR later, 3 PUSH 1, 4 .later, 5 CALL f, 6 .f, 7 PUSH 2
+    let mut vm = prepared("JMP_SCAN\n.main\nBR later\nPUSH 1\n.later\nCALL f\n.f\nPUSH 2\n");
+    assert_eq!(vm.program_counter, 1);
+
+    vm.step(&mut Default::default()).unwrap();
+    vm.step(&mut Default::default()).unwrap();
+    assert_eq!(vm.program_counter, 4);
+    vm.step(&mut Default::default()).unwrap();
+    vm.step(&mut Default::default()).unwrap();
+    assert_eq!(vm.program_counter, 6);
+    assert_eq!(vm.stack_frame_pointers.last().unwrap().from, 5);
+}
+
+#[test]
+fn single_operations_resolve_labels_through_the_jmp_table() {
+    let mut vm = prepared("JMP_SCAN\n.main\nPUSH 1\n.f\nRET\n");
+    let frames = vm.stack_frame_pointers.len();
+
+    vm.run_single_operation(Operator::CALL("f".to_string())).unwrap();
+    assert_eq!(vm.stack_frame_pointers.len(), frames + 1);
+    assert_eq!(vm.stack_frame_pointers.last().unwrap().to, 3);
+    // the program counter is put back once the operation has run
+    assert_eq!(vm.program_counter, 1);
+
+    let result = vm.run_single_operation(Operator::CALL("nowhere".to_string()));
+    assert!(matches!(result, Err(VmError::UnknownLabel { pc: 5, .. })));
+    assert_eq!(vm.program.len(), 5);
+}
@@ -0,0 +1,69 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+use stalfos_vm::asm_parser::asm_parser::parse_string;
+use stalfos_vm::stalfos::VM;
+
+// a sink the test can still read after handing a clone of it to the VM
+#[derive(Clone, Default)]
+struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Capture {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+fn program(source: &str) -> Vec<stalfos_vm::ops::ops::Operator> {
+    parse_string(source.to_string()).unwrap().1
+}
+
+#[test]
+fn console_captures_print_syscalls() {
+    let console = Capture::default();
+    let mut vm = VM::new();
+    vm.console = Box::new(console.clone());
+
+    let source = "JMP_SCAN\n.main\nPUSH 42\nSYSCALL 1 1\nCONST_S 1 \"hi\"\nLOADD 1\nSYSCALLD 3\nPUSH 7\nSYSCALL 2 1\n";
+    vm.execute_program(program(source)).unwrap();
+
+    assert_eq!(console.contents(), "42\nhi\nVM ended with exit code 7\n");
+}
+
+#[test]
+fn trace_is_separate_from_console() {
+    let console = Capture::default();
+    let trace = Capture::default();
+    let mut vm = VM::new_debug();
+    vm.console = Box::new(console.clone());
+    vm.trace = Box::new(trace.clone());
+
+    vm.execute_program(program("JMP_SCAN\n.main\nPUSH 1\nSYSCALL 1 1\nSYSCALL 2 0\n"))
+        .unwrap();
+
+    assert_eq!(console.contents(), "1\nVM ended with exit code 1\n");
+    assert_eq!(trace.contents(), "found label main at position 1\n2\n3\n4\n5\n");
+}
+
+#[test]
+fn syscall_4_prints_floats() {
+    let console = Capture::default();
+    let mut vm = VM::new();
+    vm.console = Box::new(console.clone());
+
+    let source = "JMP_SCAN\n.main\nCONST_F 1 -1.25\nLOAD_CONST 1\nSYSCALL 4 1\nPUSH 3\nUTOF\nSYSCALL 4 1\nSYSCALL 2 0\n";
+    vm.execute_program(program(source)).unwrap();
+
+    assert_eq!(console.contents(), "-1.25\n3\nVM ended with exit code 1\n");
+}
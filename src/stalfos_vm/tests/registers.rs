@@ -0,0 +1,143 @@
+mod common;
+
+use common::run;
+use stalfos_vm::stalfos::{VmError, VM};
+use std::cmp::Ordering;
+
+#[test]
+fn adds_carry_between_words() {
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        PUSH 4294967295
+        PUSH 0
+        SETREG64 0
+        PUSH 1
+        PUSH 0
+        SETREG64 1
+        ADD64u
+        GETREG64 0
+        PUSH 3
+        PUSH 0
+        SETREG64 1
+        MUL64u
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    // low word, then the high word on top
+    assert_eq!(vm.stack, vec![0, 1]);
+    assert_eq!(vm.register64(0), 3 << 32);
+    assert!(!vm.signal_overflow);
+}
+
+#[test]
+fn overflow_is_signalled() {
+    // u64::MAX + 1 wraps, then i64::MAX + 1 overflows as a signed add
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        PUSH 4294967295
+        PUSH 4294967295
+        SETREG64 0
+        PUSH 1
+        PUSH 0
+        SETREG64 1
+        ADD64u
+        BRo unsigned
+        SYSCALL 2 0
+        .unsigned
+        PUSH 4294967295
+        PUSH 2147483647
+        SETREG64 0
+        ADD64i
+        BRo signed
+        SYSCALL 2 0
+        .signed
+        PUSH 7
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![7]);
+    assert_eq!(vm.register64(0), 1 << 63);
+}
+
+#[test]
+fn shifts_and_compares_64() {
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        PUSH 1
+        PUSH 0
+        SETREG64 0
+        PUSH 1
+        PUSH 2147483648
+        SETREG64 1
+        CMP64u
+        BRlt unsigned_less
+        SYSCALL 2 0
+        .unsigned_less
+        CMP64i
+        BRgt signed_greater
+        SYSCALL 2 0
+        .signed_greater
+        PUSH 40
+        LSL64
+        GETREG64 0
+        PUSH 0
+        PUSH 2147483648
+        SETREG64 0
+        PUSH 63
+        ASR64
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(vm.stack, vec![0, 1 << 8]);
+    assert_eq!(vm.register64(0), u64::MAX);
+    assert_eq!(vm.signal_compare, Some(Ordering::Greater));
+}
+
+#[test]
+fn arithmetic_128() {
+    let mut vm = VM::new();
+    run(
+        &mut vm,
+        "
+        PUSH 0
+        PUSH 1
+        PUSH 0
+        PUSH 0
+        SETREG128 0
+        PUSH 5
+        PUSH 0
+        PUSH 0
+        PUSH 0
+        SETREG128 1
+        MUL128u
+        PUSH 64
+        LSL128
+        GETREG128 0
+        SYSCALL 2 0
+        ",
+    )
+    .unwrap();
+
+    // 2^32 * 5 << 64, least significant word first
+    assert_eq!(vm.stack, vec![0, 0, 0, 5]);
+    assert_eq!(vm.register128(0), 5u128 << 96);
+
+    let mut vm = VM::new();
+    let result = run(&mut vm, "DIV128u\nSYSCALL 2 0\n");
+    assert!(matches!(result, Err(VmError::DivideByZero { pc: 2, .. })));
+
+    let result = run(&mut VM::new(), "GETREG64 2\nSYSCALL 2 0\n");
+    assert!(matches!(result, Err(VmError::OutOfBounds { index: 2, len: 2, .. })));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::run_to_exit;
+use stalfos_vm::stalfos::{VmError, VM};
+
+fn stack_after(source: &str) -> Result<Vec<u32>, VmError> {
+    let mut vm = VM::new();
+    run_to_exit(&mut vm, source)?;
+    Ok(vm.stack)
+}
+
+#[test]
+fn immediate_shifts_and_rotates() {
+    let stack = stack_after(
+        "
+        PUSH 2147483649
+        RORC 4
+        PUSH 2147483649
+        ROLC 4
+        PUSH 2147483648
+        LSRC 31
+        PUSH 2147483648
+        ASRC 31
+        PUSH 3
+        LSLC 30
+        PUSH 3
+        ASLC 31
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(
+        stack,
+        vec![0x1800_0000, 0x0000_0018, 1, u32::MAX, 0xC000_0000, 0x8000_0000]
+    );
+}
+
+#[test]
+fn stack_shifts_pop_the_amount_first() {
+    let stack = stack_after(
+        "
+        PUSH 1
+        PUSH 12
+        LSLD
+        PUSH 4096
+        PUSH 12
+        LSRD
+        PUSH 4026531840
+        PUSH 4
+        ASRD
+        PUSH 15
+        PUSH 2
+        RORD
+        PUSH 15
+        PUSH 30
+        ROLD
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(stack, vec![4096, 1, 0xFF00_0000, 0xC000_0003, 0xC000_0003]);
+}
+
+#[test]
+fn amounts_of_32_and_over_are_masked() {
+    // 33 shifts by 1, 32 by 0
+    let stack = stack_after(
+        "
+        PUSH 1
+        LSLC 33
+        PUSH 6
+        PUSH 32
+        LSRD
+        PUSH 2147483648
+        ASRC 64
+        PUSH 1
+        RORC 33
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(stack, vec![2, 6, 0x8000_0000, 0x8000_0000]);
+}
+
+#[test]
+fn leading_and_trailing_zeros() {
+    let stack = stack_after("PUSH 256\nCLZ\nPUSH 256\nCTZ\nPUSH 0\nCLZ\nPUSH 0\nCTZ\nPUSH 4294967295\nCLZ").unwrap();
+    assert_eq!(stack, vec![23, 8, 32, 32, 0]);
+
+    assert!(matches!(stack_after("CLZ"), Err(VmError::StackUnderflow { pc: 2, .. })));
+}
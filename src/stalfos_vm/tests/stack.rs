@@ -0,0 +1,56 @@
+mod common;
+
+use common::run_to_exit;
+use stalfos_vm::stalfos::{VmError, VM};
+
+fn stack_after(source: &str) -> (Result<(), VmError>, Vec<u32>) {
+    let mut vm = VM::new();
+    let result = run_to_exit(&mut vm, source);
+    (result, vm.stack)
+}
+
+#[test]
+fn over_rot_pick_and_roll() {
+    let (result, stack) = stack_after("PUSH 1\nPUSH 2\nPUSH 3\nOVER\nROT\nPICK 0\nPICK 4\nROLL 3");
+    result.unwrap();
+    // 1 2 3 -> 1 2 3 2 -> 1 3 2 2 -> 1 3 2 2 2 -> 1 3 2 2 2 1 -> 1 3 2 2 1 2
+    assert_eq!(stack, vec![1, 3, 2, 2, 1, 2]);
+
+    let (_, stack) = stack_after("PUSH 1\nPUSH 2\nROLL 1\nPUSH 3\nROLL 0");
+    assert_eq!(stack, vec![2, 1, 3]);
+}
+
+#[test]
+fn drop_depth_and_clear() {
+    let (result, stack) = stack_after("PUSH 1\nPUSH 2\nPUSH 3\nDEPTH\nDROP 2\nDROP 0\nDEPTH");
+    result.unwrap();
+    assert_eq!(stack, vec![1, 2, 2]);
+
+    let (result, stack) = stack_after("PUSH 1\nPUSH 2\nCLEAR\nDEPTH\nCLEAR");
+    result.unwrap();
+    assert!(stack.is_empty());
+}
+
+#[test]
+fn not_enough_values_underflows_and_leaves_the_stack() {
+    for source in ["ROT", "PICK 2", "ROLL 2", "DROP 3", "PICK 18446744073709551615"] {
+        let (result, stack) = stack_after(&format!("PUSH 1\nPUSH 2\n{}", source));
+        assert!(matches!(result, Err(VmError::StackUnderflow { pc: 4, .. })), "{}", source);
+        assert_eq!(stack, vec![1, 2], "{}", source);
+    }
+
+    let (result, stack) = stack_after("PUSH 1\nOVER");
+    assert!(matches!(result, Err(VmError::StackUnderflow { pc: 3, .. })));
+    assert_eq!(stack, vec![1]);
+}
+
+#[test]
+fn dupo_counts_from_1_at_the_top() {
+    let (result, stack) = stack_after("PUSH 1\nPUSH 2\nDUPO 1\nDUPO 3");
+    result.unwrap();
+    assert_eq!(stack, vec![1, 2, 2, 1]);
+
+    let (result, stack) = stack_after("PUSH 1\nDUPO 0");
+    assert!(matches!(result, Err(VmError::OutOfBounds { index: 1, len: 1, .. })));
+    assert_eq!(stack, vec![1]);
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{run, run_to_exit};
+use stalfos_vm::stalfos::{SyscallHandler, VmError, VM};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// a handler that records the args of every call into calls
+fn recording(calls: &Rc<RefCell<Vec<Vec<u32>>>>) -> SyscallHandler {
+    let calls = Rc::clone(calls);
+    Box::new(move |_, args| {
+        calls.borrow_mut().push(args);
+        Ok(true)
+    })
+}
+
+#[test]
+fn registered_syscalls_get_their_args_in_push_order() {
+    let calls = Rc::new(RefCell::new(vec![]));
+    let mut vm = VM::new();
+    assert!(vm.register_syscall(10, recording(&calls)).is_none());
+
+    run_to_exit(&mut vm, "PUSH 1\nPUSH 2\nPUSH 3\nSYSCALL 10 2\nPUSH 4\nPUSH 5\nPUSH 2\nSYSCALLD 10")
+        .unwrap();
+    assert_eq!(*calls.borrow(), vec![vec![2, 3], vec![4, 5]]);
+    // only the args are popped
+    assert_eq!(vm.stack, vec![1]);
+}
+
+#[test]
+fn handlers_can_change_the_vm_and_finish_the_program() {
+    let mut vm = VM::new();
+    vm.register_syscall(
+        10,
+        Box::new(|vm, args| {
+            vm.stack.push(args.iter().sum());
+            Ok(true)
+        }),
+    );
+    vm.register_syscall(11, Box::new(|_, _| Ok(false)));
+
+    // nothing after SYSCALL 11 runs
+    run(&mut vm, "PUSH 20\nPUSH 22\nSYSCALL 10 2\nSYSCALL 11 0\nPUSH 1\n").unwrap();
+    assert_eq!(vm.stack, vec![42]);
+    assert!(vm.signal_finished);
+}
+
+#[test]
+fn handler_errors_stop_the_program() {
+    let mut vm = VM::new();
+    vm.register_syscall(
+        10,
+        Box::new(|vm, _| Err(VmError::InvalidProgramCounter { pc: vm.program_counter })),
+    );
+
+    let result = run_to_exit(&mut vm, "PUSH 1\nSYSCALL 10 0");
+    assert!(matches!(result, Err(VmError::InvalidProgramCounter { pc: 3 })));
+}
+
+#[test]
+fn built_in_syscalls_can_be_overridden() {
+    let calls = Rc::new(RefCell::new(vec![]));
+    let mut vm = VM::new();
+    // syscall 1 prints a number
+    assert!(vm.register_syscall(1, recording(&calls)).is_some());
+
+    run_to_exit(&mut vm, "PUSH 7\nSYSCALL 1 1").unwrap();
+    assert_eq!(*calls.borrow(), vec![vec![7]]);
+}
+
+#[test]
+fn removed_syscalls_are_unknown() {
+    let mut vm = VM::new();
+    assert!(vm.remove_syscall(1).is_some());
+    assert!(vm.remove_syscall(1).is_none());
+
+    let result = run_to_exit(&mut vm, "PUSH 7\nSYSCALL 1 1");
+    assert!(matches!(result, Err(VmError::UnknownSyscall { pc: 3, syscall_id: 1, .. })));
+}
+
+#[test]
+fn a_handler_can_replace_itself() {
+    let calls = Rc::new(RefCell::new(vec![]));
+    let replacement = Rc::clone(&calls);
+    let mut vm = VM::new();
+    vm.register_syscall(
+        10,
+        Box::new(move |vm, _| {
+            vm.register_syscall(10, recording(&replacement));
+            Ok(true)
+        }),
+    );
+
+    run_to_exit(&mut vm, "PUSH 1\nSYSCALL 10 1\nPUSH 2\nSYSCALL 10 1\nPUSH 3\nSYSCALL 10 1").unwrap();
+    // the first call only swapped the handler
+    assert_eq!(*calls.borrow(), vec![vec![2], vec![3]]);
+}
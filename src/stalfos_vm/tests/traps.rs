@@ -0,0 +1,79 @@
+mod common;
+
+use common::run_program;
+use stalfos_vm::exceptions::exceptions::{DIVIDE_BY_ZERO, OUT_OF_BOUNDS, STACK_UNDERFLOW};
+use stalfos_vm::stalfos::{VmError, VM};
+
+// the catch in main pushes the code of the fault and returns from main
+const FAULTS: &str = "
+    .main
+    EXCEPT_CATCH_RANGE fault 0xFFFF0000 0xFFFFFFFF
+    CALL divide
+    CALL get_byte
+    CALL underflow
+    SYSCALL 2 0
+    .fault
+    EXCEPT_CODE
+    EXCEPT_END
+    RET
+    .divide
+    PUSH 0
+    PUSH 5
+    DIVu
+    RET
+    .get_byte
+    CONST_U 1 7
+    GETBYTE 1 4
+    RET
+    .underflow
+    POP
+    RET
+";
+
+#[test]
+fn each_fault_has_its_own_code() {
+    for (function, code) in [
+        ("divide", DIVIDE_BY_ZERO),
+        ("get_byte", OUT_OF_BOUNDS),
+        ("underflow", STACK_UNDERFLOW),
+    ] {
+        let mut vm = VM::new();
+        vm.trap_faults = true;
+        let source = FAULTS.replace(
+            "CALL divide\n    CALL get_byte\n    CALL underflow\n",
+            &format!("CALL {}\n", function),
+        );
+        run_program(&mut vm, &source).unwrap();
+
+        assert_eq!(vm.stack, vec![code], "{}", function);
+        assert!(vm.stack_frame_pointers.is_empty());
+    }
+}
+
+#[test]
+fn uncaught_faults_reach_the_host() {
+    let mut vm = VM::new();
+    vm.trap_faults = true;
+    // 0 JMP_SCAN, 1 .main, 2 EXCEPT_CATCH_RANGE, 3 CALL f, 4 .handler, 5 RET, 6 .f, 7 ALLOC, 8 POP
+    let result = run_program(
+        &mut vm,
+        ".main\nEXCEPT_CATCH_RANGE handler 0 9\nCALL f\n.handler\nRET\n.f\nALLOC 1 2\nPOP\n",
+    );
+    assert!(matches!(result, Err(VmError::StackUnderflow { pc: 8, .. })));
+
+    // nothing is unwound, the host sees the VM as it was when the fault happened
+    assert_eq!(vm.program_counter, 8);
+    assert_eq!(vm.stack_frame_pointers.len(), 2);
+    assert_eq!(vm.stack_frame_pointers[1].to, 6);
+    assert!(vm.static_alloc_table.contains_key(&1));
+    assert!(vm.exception.is_none());
+}
+
+#[test]
+fn faults_stop_the_vm_without_trap_faults() {
+    let mut vm = VM::new();
+    let result = run_program(&mut vm, FAULTS);
+
+    assert!(matches!(result, Err(VmError::DivideByZero { pc: 14, .. })));
+    assert!(vm.exception.is_none());
+}